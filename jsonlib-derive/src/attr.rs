@@ -0,0 +1,307 @@
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, Fields, Ident, LitStr, Path, Result};
+
+/// How `rename_all` turns Rust names into JSON names.
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    Camel,
+    Pascal,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    Lower,
+    Upper,
+}
+
+impl RenameRule {
+    fn parse(meta: &ParseNestedMeta) -> Result<RenameRule> {
+        let lit: LitStr = meta.value()?.parse()?;
+        let rule = match lit.value().as_str() {
+            "camelCase" => RenameRule::Camel,
+            "PascalCase" => RenameRule::Pascal,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "expected one of camelCase, PascalCase, snake_case, \
+                     SCREAMING_SNAKE_CASE, kebab-case, lowercase or UPPERCASE",
+                ))
+            }
+        };
+        Ok(rule)
+    }
+
+    pub fn apply(self, name: &str) -> String {
+        let words = split_words(name);
+        match self {
+            RenameRule::Camel => {
+                let mut out = String::new();
+                for (i, word) in words.iter().enumerate() {
+                    if i == 0 {
+                        out.push_str(word);
+                    } else {
+                        out.push_str(&capitalize(word));
+                    }
+                }
+                out
+            }
+            RenameRule::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+            RenameRule::Snake => words.join("_"),
+            RenameRule::ScreamingSnake => words.join("_").to_uppercase(),
+            RenameRule::Kebab => words.join("-"),
+            RenameRule::Lower => words.concat(),
+            RenameRule::Upper => words.concat().to_uppercase(),
+        }
+    }
+}
+
+/// Lowercase words of a field or variant name, split at underscores and before capitals
+/// that follow a lowercase letter or digit, so `user_id` and `UserId` both give `user`, `id`.
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut word));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// The name of a field or variant in JSON.
+pub fn json_name(ident: &Ident, rename: &Option<String>, rule: Option<RenameRule>) -> String {
+    if let Some(rename) = rename {
+        return rename.clone();
+    }
+    // Raw identifiers like r#type are written without the r#
+    let name = ident.unraw().to_string();
+    match rule {
+        Some(rule) => rule.apply(&name),
+        None => name,
+    }
+}
+
+/// How the variant of an enum is stored.
+pub enum Tagging {
+    /// `{"Variant": content}`, or just `"Variant"` for unit variants
+    External,
+    /// `{"tag": "Variant", ...fields}`, a newtype variant has to contain an object
+    Internal { tag: String },
+    /// `{"tag": "Variant", "content": content}`
+    Adjacent { tag: String, content: String },
+    /// Only the content, the first variant that converts wins
+    Untagged,
+}
+
+/// `#[json(...)]` on a struct or enum.
+pub struct Container {
+    pub rename_all: Option<RenameRule>,
+    pub tagging: Tagging,
+}
+
+impl Container {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Container> {
+        let mut rename_all = None;
+        let mut tag = None;
+        let mut content = None;
+        let mut untagged = false;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    rename_all = Some(RenameRule::parse(&meta)?);
+                } else if meta.path.is_ident("tag") {
+                    tag = Some(string_value(&meta)?);
+                } else if meta.path.is_ident("content") {
+                    content = Some(string_value(&meta)?);
+                } else if meta.path.is_ident("untagged") {
+                    untagged = true;
+                } else {
+                    return Err(meta.error("unknown json attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        let tagging = match (tag, content, untagged) {
+            (None, None, false) => Tagging::External,
+            (Some(tag), None, false) => Tagging::Internal { tag },
+            (Some(tag), Some(content), false) => Tagging::Adjacent { tag, content },
+            (None, None, true) => Tagging::Untagged,
+            (None, Some(_), false) => {
+                return Err(syn::Error::new_spanned(
+                    &attrs[0],
+                    "`content` needs a `tag` to go with it",
+                ))
+            }
+            (_, _, true) => {
+                return Err(syn::Error::new_spanned(
+                    &attrs[0],
+                    "`untagged` can not be combined with `tag` or `content`",
+                ))
+            }
+        };
+        Ok(Container {
+            rename_all,
+            tagging,
+        })
+    }
+}
+
+/// `#[json(...)]` on an enum variant.
+pub struct Variant {
+    pub rename: Option<String>,
+    /// Applies to the fields of a struct variant
+    pub rename_all: Option<RenameRule>,
+}
+
+impl Variant {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Variant> {
+        let mut variant = Variant {
+            rename: None,
+            rename_all: None,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    variant.rename = Some(string_value(&meta)?);
+                } else if meta.path.is_ident("rename_all") {
+                    variant.rename_all = Some(RenameRule::parse(&meta)?);
+                } else {
+                    return Err(meta.error("unknown json attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(variant)
+    }
+}
+
+/// What a missing field is filled with.
+pub enum FieldDefault {
+    /// `Default::default()`
+    Trait,
+    /// A function without arguments
+    Path(Path),
+}
+
+/// `#[json(...)]` on a field.
+pub struct Field {
+    pub rename: Option<String>,
+    pub default: Option<FieldDefault>,
+    pub skip: bool,
+    pub skip_serializing_if: Option<Path>,
+    pub flatten: bool,
+}
+
+impl Field {
+    /// The attributes of every field, only named fields can have any.
+    pub fn from_fields(fields: &Fields) -> Result<Vec<Field>> {
+        fields
+            .iter()
+            .map(|field| {
+                if field.ident.is_none() {
+                    if let Some(attr) = field.attrs.iter().find(|attr| attr.path().is_ident("json"))
+                    {
+                        return Err(syn::Error::new_spanned(
+                            attr,
+                            "json attributes are only supported on named fields",
+                        ));
+                    }
+                }
+                Field::from_attrs(&field.attrs)
+            })
+            .collect()
+    }
+
+    fn from_attrs(attrs: &[Attribute]) -> Result<Field> {
+        let mut field = Field {
+            rename: None,
+            default: None,
+            skip: false,
+            skip_serializing_if: None,
+            flatten: false,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    field.rename = Some(string_value(&meta)?);
+                } else if meta.path.is_ident("default") {
+                    field.default = Some(if meta.input.peek(syn::Token![=]) {
+                        FieldDefault::Path(path_value(&meta)?)
+                    } else {
+                        FieldDefault::Trait
+                    });
+                } else if meta.path.is_ident("skip") {
+                    field.skip = true;
+                } else if meta.path.is_ident("skip_serializing_if") {
+                    field.skip_serializing_if = Some(path_value(&meta)?);
+                } else if meta.path.is_ident("flatten") {
+                    field.flatten = true;
+                } else {
+                    return Err(meta.error("unknown json attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(field)
+    }
+}
+
+fn string_value(meta: &ParseNestedMeta) -> Result<String> {
+    let lit: LitStr = meta.value()?.parse()?;
+    Ok(lit.value())
+}
+
+/// A path given as a string, like `skip_serializing_if = "Vec::is_empty"`.
+fn path_value(meta: &ParseNestedMeta) -> Result<Path> {
+    let lit: LitStr = meta.value()?.parse()?;
+    lit.parse()
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_rename_rules() {
+        for (rule, field, variant) in [
+            (RenameRule::Camel, "userId", "userId"),
+            (RenameRule::Pascal, "UserId", "UserId"),
+            (RenameRule::Snake, "user_id", "user_id"),
+            (RenameRule::ScreamingSnake, "USER_ID", "USER_ID"),
+            (RenameRule::Kebab, "user-id", "user-id"),
+            (RenameRule::Lower, "userid", "userid"),
+            (RenameRule::Upper, "USERID", "USERID"),
+        ] {
+            assert_eq!(rule.apply("user_id"), field);
+            assert_eq!(rule.apply("UserId"), variant);
+        }
+        assert_eq!(RenameRule::Camel.apply("http_2_server"), "http2Server");
+        assert_eq!(RenameRule::Kebab.apply("Ipv4Addr"), "ipv4-addr");
+    }
+}
@@ -0,0 +1,270 @@
+use crate::attr::{self, json_name, Container, FieldDefault, RenameRule, Tagging};
+use crate::{add_bound, binding};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DataEnum, DeriveInput, Fields, Result};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+    let body = match &input.data {
+        Data::Struct(data) => from_fields(quote!(Self), &data.fields, container.rename_all)?,
+        Data::Enum(data) => {
+            let expected = format!("a variant of {}", input.ident);
+            from_variants(&container, data, &expected)?
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "unions can not be converted from JSON",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let generics = add_bound(&input.generics, parse_quote!(::jsonlib::FromJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::jsonlib::FromJson for #name #ty_generics #where_clause {
+            fn from_json(
+                value: &::jsonlib::value::Value,
+            ) -> ::core::result::Result<Self, ::jsonlib::Error> {
+                #body
+            }
+        }
+    })
+}
+
+/// Statements that read `value` into `constructor` and return it, or return an error
+/// with `?`. Named fields come from an object, tuple fields from an array of the same
+/// length, a single tuple field from the value itself and unit structs from `null`.
+fn from_fields(
+    constructor: TokenStream,
+    fields: &Fields,
+    rule: Option<RenameRule>,
+) -> Result<TokenStream> {
+    let attrs = attr::Field::from_fields(fields)?;
+    let tokens = match fields {
+        Fields::Named(named) => {
+            let reads_object = attrs.iter().any(|attrs| !attrs.skip && !attrs.flatten);
+            let object = if reads_object {
+                quote!(let object = value.as_object().ok_or_else(|| value.type_error("an object"))?;)
+            } else {
+                quote!(value.as_object().ok_or_else(|| value.type_error("an object"))?;)
+            };
+            let inits = named.named.iter().zip(&attrs).map(|(field, attrs)| {
+                // Named fields always have an ident
+                let ident = field.ident.as_ref().unwrap();
+                let key = json_name(ident, &attrs.rename, rule);
+                let default = match &attrs.default {
+                    Some(FieldDefault::Path(path)) => quote!(#path()),
+                    Some(FieldDefault::Trait) | None => quote!(::core::default::Default::default()),
+                };
+                let init = if attrs.skip {
+                    default
+                } else if attrs.flatten {
+                    quote!(::jsonlib::FromJson::from_json(value)?)
+                } else {
+                    // Types that read `null`, like `Option`, can be left out without a default
+                    let missing = match &attrs.default {
+                        Some(_) => default,
+                        None => quote! {
+                            ::jsonlib::FromJson::from_json(&::jsonlib::value::Value::Null).map_err(|_| {
+                                ::jsonlib::Error::MissingField {
+                                    field: #key,
+                                    path: ::std::string::String::new(),
+                                }
+                            })?
+                        },
+                    };
+                    quote! {
+                        match object.get(#key) {
+                            ::core::option::Option::Some(member) => ::jsonlib::FromJson::from_json(member)
+                                .map_err(|e| e.with_key(#key))?,
+                            ::core::option::Option::None => #missing,
+                        }
+                    }
+                };
+                quote!(#ident: #init)
+            });
+            quote! {
+                #object
+                ::core::result::Result::Ok(#constructor { #(#inits),* })
+            }
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => quote! {
+            ::core::result::Result::Ok(#constructor(::jsonlib::FromJson::from_json(value)?))
+        },
+        Fields::Unnamed(unnamed) => {
+            let len = unnamed.unnamed.len();
+            let bindings: Vec<_> = (0..len).map(binding).collect();
+            let indices = 0..len;
+            quote! {
+                let arr = value.as_array().ok_or_else(|| value.type_error("an array"))?;
+                if arr.len() != #len {
+                    return ::core::result::Result::Err(::jsonlib::Error::InvalidLength {
+                        expected: #len,
+                        found: arr.len(),
+                        path: ::std::string::String::new(),
+                    });
+                }
+                #(
+                    let #bindings = ::jsonlib::FromJson::from_json(&arr[#indices])
+                        .map_err(|e| e.with_index(#indices))?;
+                )*
+                ::core::result::Result::Ok(#constructor(#(#bindings),*))
+            }
+        }
+        Fields::Unit => quote! {
+            <() as ::jsonlib::FromJson>::from_json(value)?;
+            ::core::result::Result::Ok(#constructor)
+        },
+    };
+    Ok(tokens)
+}
+
+fn from_variants(container: &Container, data: &DataEnum, expected: &str) -> Result<TokenStream> {
+    let mut names = Vec::new();
+    let mut units = Vec::new();
+    let mut blocks = Vec::new();
+    for variant in &data.variants {
+        let attrs = attr::Variant::from_attrs(&variant.attrs)?;
+        let ident = &variant.ident;
+        if let (Tagging::Internal { .. }, Fields::Unnamed(unnamed)) =
+            (&container.tagging, &variant.fields)
+        {
+            if unnamed.unnamed.len() != 1 {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "internally tagged enums can not have tuple variants",
+                ));
+            }
+        }
+        names.push(json_name(ident, &attrs.rename, container.rename_all));
+        units.push(matches!(variant.fields, Fields::Unit));
+        blocks.push(from_fields(
+            quote!(Self::#ident),
+            &variant.fields,
+            attrs.rename_all,
+        )?);
+    }
+    let constructors = data.variants.iter().map(|variant| &variant.ident);
+
+    let unknown = quote! {
+        ::jsonlib::Error::UnknownVariant {
+            found: name.to_string(),
+            path: ::std::string::String::new(),
+        }
+    };
+    let tokens = match &container.tagging {
+        Tagging::External => {
+            let unit_arms = names
+                .iter()
+                .zip(constructors)
+                .zip(&units)
+                .filter(|(_, unit)| **unit)
+                .map(
+                    |((name, ident), _)| quote!(#name => ::core::result::Result::Ok(Self::#ident),),
+                );
+            quote! {
+                match value {
+                    ::jsonlib::value::Value::String(name) => match name.as_str() {
+                        #(#unit_arms)*
+                        _ => ::core::result::Result::Err(#unknown),
+                    },
+                    ::jsonlib::value::Value::Object(object) if object.len() == 1 => {
+                        // The object has one member
+                        let (name, value) = object.get_index(0).unwrap();
+                        let result = match name.as_str() {
+                            #(#names => (|| -> ::core::result::Result<Self, ::jsonlib::Error> {
+                                #blocks
+                            })(),)*
+                            _ => return ::core::result::Result::Err(#unknown),
+                        };
+                        result.map_err(|e| e.with_key(name))
+                    }
+                    _ => ::core::result::Result::Err(value.type_error(#expected)),
+                }
+            }
+        }
+        Tagging::Internal { tag } => {
+            // Unit variants are only the tag, the others read the object around it
+            let arms = names.iter().zip(constructors).zip(&units).zip(&blocks).map(
+                |(((name, ident), unit), block)| {
+                    if *unit {
+                        quote!(#name => ::core::result::Result::Ok(Self::#ident),)
+                    } else {
+                        quote!(#name => { #block })
+                    }
+                },
+            );
+            let read_tag = read_tag(tag, expected);
+            quote! {
+                #read_tag
+                match name {
+                    #(#arms)*
+                    _ => ::core::result::Result::Err(#unknown.with_key(#tag)),
+                }
+            }
+        }
+        Tagging::Adjacent { tag, content } => {
+            let arms = names.iter().zip(constructors).zip(&units).zip(&blocks).map(
+                |(((name, ident), unit), block)| {
+                    if *unit {
+                        return quote!(#name => ::core::result::Result::Ok(Self::#ident),);
+                    }
+                    quote!(#name => {
+                        let value = match object.get(#content) {
+                            ::core::option::Option::Some(value) => value,
+                            ::core::option::Option::None => {
+                                return ::core::result::Result::Err(::jsonlib::Error::MissingField {
+                                    field: #content,
+                                    path: ::std::string::String::new(),
+                                })
+                            }
+                        };
+                        (|| -> ::core::result::Result<Self, ::jsonlib::Error> { #block })()
+                            .map_err(|e| e.with_key(#content))
+                    })
+                },
+            );
+            let read_tag = read_tag(tag, expected);
+            quote! {
+                #read_tag
+                match name {
+                    #(#arms)*
+                    _ => ::core::result::Result::Err(#unknown.with_key(#tag)),
+                }
+            }
+        }
+        Tagging::Untagged => quote! {
+            #(
+                if let ::core::result::Result::Ok(result) =
+                    (|| -> ::core::result::Result<Self, ::jsonlib::Error> { #blocks })()
+                {
+                    return ::core::result::Result::Ok(result);
+                }
+            )*
+            ::core::result::Result::Err(value.type_error(#expected))
+        },
+    };
+    Ok(tokens)
+}
+
+/// Binds `object` to the object in `value` and `name` to its `tag` member.
+fn read_tag(tag: &str, expected: &str) -> TokenStream {
+    quote! {
+        let object = value.as_object().ok_or_else(|| value.type_error(#expected))?;
+        let name = match object.get(#tag) {
+            ::core::option::Option::Some(name) => name
+                .as_str()
+                .ok_or_else(|| name.type_error("a string").with_key(#tag))?,
+            ::core::option::Option::None => {
+                return ::core::result::Result::Err(::jsonlib::Error::MissingField {
+                    field: #tag,
+                    path: ::std::string::String::new(),
+                })
+            }
+        };
+    }
+}
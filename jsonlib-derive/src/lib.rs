@@ -0,0 +1,40 @@
+//! `#[derive(ToJson, FromJson)]` for jsonlib, use it through jsonlib's `derive` feature.
+
+mod attr;
+mod de;
+mod ser;
+
+use proc_macro::TokenStream;
+use proc_macro2::Ident;
+use quote::format_ident;
+use syn::{parse_macro_input, DeriveInput, Generics, TypeParamBound};
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    ser::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    de::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The variable the field at `index` is bound to in generated code.
+fn binding(index: usize) -> Ident {
+    format_ident!("__field{}", index)
+}
+
+/// Requires `bound` of every type parameter, e.g. `T: ToJson` to derive `ToJson`.
+fn add_bound(generics: &Generics, bound: TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(bound.clone());
+    }
+    generics
+}
@@ -0,0 +1,193 @@
+use crate::attr::{self, json_name, Container, RenameRule, Tagging};
+use crate::{add_bound, binding};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Fields, Result};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = attr::Field::from_fields(&data.fields)?;
+            let pattern = pattern(quote!(Self), &data.fields, &fields);
+            let content = content(&data.fields, &fields, container.rename_all, None);
+            quote!(match self { #pattern => #content })
+        }
+        Data::Enum(data) => {
+            let mut arms = TokenStream::new();
+            for variant in &data.variants {
+                arms.extend(variant_arm(&container, &input.ident, variant)?);
+            }
+            if data.variants.is_empty() {
+                quote!(match *self {})
+            } else {
+                quote!(match self { #arms })
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "unions can not be converted to JSON",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let generics = add_bound(&input.generics, parse_quote!(::jsonlib::ToJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::jsonlib::ToJson for #name #ty_generics #where_clause {
+            fn to_json(&self) -> ::jsonlib::value::Value {
+                #body
+            }
+        }
+    })
+}
+
+fn variant_arm(
+    container: &Container,
+    enum_name: &syn::Ident,
+    variant: &syn::Variant,
+) -> Result<TokenStream> {
+    let attrs = attr::Variant::from_attrs(&variant.attrs)?;
+    let fields = attr::Field::from_fields(&variant.fields)?;
+    let name = json_name(&variant.ident, &attrs.rename, container.rename_all);
+    let ident = &variant.ident;
+    let pattern = pattern(quote!(Self::#ident), &variant.fields, &fields);
+    let content_with = |tag| content(&variant.fields, &fields, attrs.rename_all, tag);
+
+    let body = match (&container.tagging, &variant.fields) {
+        (Tagging::External, Fields::Unit) => quote!(::jsonlib::value::Value::from(#name)),
+        (Tagging::External, _) => {
+            let content = content_with(None);
+            quote!({
+                let mut object = ::jsonlib::Map::new();
+                object.insert(#name, #content);
+                ::jsonlib::value::Value::Object(object)
+            })
+        }
+        (Tagging::Internal { tag }, Fields::Named(_)) => content_with(Some((tag, &name))),
+        (Tagging::Internal { tag }, Fields::Unit) => quote!({
+            let mut object = ::jsonlib::Map::new();
+            object.insert(#tag, ::jsonlib::value::Value::from(#name));
+            ::jsonlib::value::Value::Object(object)
+        }),
+        (Tagging::Internal { tag }, Fields::Unnamed(unnamed)) if unnamed.unnamed.len() == 1 => {
+            // The members of the inner value go next to the tag, any other value would be lost
+            let inner = binding(0);
+            let message = format!(
+                "The internally tagged variant {}::{} has to contain an object",
+                enum_name, ident
+            );
+            quote!({
+                let mut object = ::jsonlib::Map::new();
+                object.insert(#tag, ::jsonlib::value::Value::from(#name));
+                match ::jsonlib::ToJson::to_json(#inner) {
+                    ::jsonlib::value::Value::Object(inner) => object.extend(inner),
+                    _ => panic!(#message),
+                }
+                ::jsonlib::value::Value::Object(object)
+            })
+        }
+        (Tagging::Internal { .. }, Fields::Unnamed(_)) => {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "internally tagged enums can not have tuple variants",
+            ))
+        }
+        (Tagging::Adjacent { tag, .. }, Fields::Unit) => quote!({
+            let mut object = ::jsonlib::Map::new();
+            object.insert(#tag, ::jsonlib::value::Value::from(#name));
+            ::jsonlib::value::Value::Object(object)
+        }),
+        (Tagging::Adjacent { tag, content: key }, _) => {
+            let content = content_with(None);
+            quote!({
+                let mut object = ::jsonlib::Map::new();
+                object.insert(#tag, ::jsonlib::value::Value::from(#name));
+                object.insert(#key, #content);
+                ::jsonlib::value::Value::Object(object)
+            })
+        }
+        (Tagging::Untagged, _) => content_with(None),
+    };
+    Ok(quote!(#pattern => #body,))
+}
+
+/// Binds every field that is written, e.g. `Self::Move { x: __field0, y: _ }`.
+fn pattern(path: TokenStream, fields: &Fields, attrs: &[attr::Field]) -> TokenStream {
+    let bindings = attrs.iter().enumerate().map(|(i, attrs)| {
+        if attrs.skip {
+            quote!(_)
+        } else {
+            let binding = binding(i);
+            quote!(#binding)
+        }
+    });
+    match fields {
+        Fields::Named(named) => {
+            let idents = named.named.iter().map(|field| &field.ident);
+            quote!(#path { #(#idents: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+        Fields::Unit => path,
+    }
+}
+
+/// The value the bound fields are written as. Named fields become an object, starting
+/// with the `tag` member if there is one.
+fn content(
+    fields: &Fields,
+    attrs: &[attr::Field],
+    rule: Option<RenameRule>,
+    tag: Option<(&String, &String)>,
+) -> TokenStream {
+    match fields {
+        Fields::Named(named) => {
+            let tag = tag.map(
+                |(tag, name)| quote!(object.insert(#tag, ::jsonlib::value::Value::from(#name));),
+            );
+            let inserts = named.named.iter().zip(attrs).enumerate().filter_map(
+                |(i, (field, attrs))| {
+                    if attrs.skip {
+                        return None;
+                    }
+                    let binding = binding(i);
+                    let insert = if attrs.flatten {
+                        quote! {
+                            if let ::jsonlib::value::Value::Object(inner) = ::jsonlib::ToJson::to_json(#binding) {
+                                object.extend(inner);
+                            }
+                        }
+                    } else {
+                        // Named fields always have an ident
+                        let key = json_name(field.ident.as_ref().unwrap(), &attrs.rename, rule);
+                        quote!(object.insert(#key, ::jsonlib::ToJson::to_json(#binding));)
+                    };
+                    Some(match &attrs.skip_serializing_if {
+                        Some(predicate) => quote!(if !#predicate(#binding) { #insert }),
+                        None => insert,
+                    })
+                },
+            );
+            quote!({
+                let mut object = ::jsonlib::Map::new();
+                #tag
+                #(#inserts)*
+                ::jsonlib::value::Value::Object(object)
+            })
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            let inner = binding(0);
+            quote!(::jsonlib::ToJson::to_json(#inner))
+        }
+        Fields::Unnamed(unnamed) => {
+            let bindings = (0..unnamed.unnamed.len()).map(binding);
+            quote!(::jsonlib::value::Value::Array(vec![
+                #(::jsonlib::ToJson::to_json(#bindings)),*
+            ]))
+        }
+        Fields::Unit => quote!(::jsonlib::value::Value::Null),
+    }
+}
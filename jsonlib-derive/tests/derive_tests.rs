@@ -0,0 +1,325 @@
+use jsonlib::{json, Error, FromJson, ToJson};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(rename_all = "camelCase")]
+struct User {
+    user_id: u64,
+    display_name: String,
+    #[json(rename = "mail")]
+    email: Option<String>,
+    #[json(default)]
+    roles: Vec<String>,
+    #[json(skip)]
+    session: Option<u32>,
+    #[json(skip_serializing_if = "BTreeMap::is_empty", default)]
+    labels: BTreeMap<String, String>,
+}
+
+#[test]
+fn test_struct_round_trip() {
+    let user = User {
+        user_id: 7,
+        display_name: "Mike".to_string(),
+        email: None,
+        roles: vec!["admin".to_string()],
+        session: Some(3),
+        labels: BTreeMap::new(),
+    };
+    let value = user.to_json();
+    assert_eq!(
+        value,
+        json!({"userId": 7, "displayName": "Mike", "mail": null, "roles": ["admin"]})
+    );
+    assert_eq!(
+        User::from_json(&value),
+        Ok(User {
+            session: None,
+            ..user
+        })
+    );
+}
+
+#[test]
+fn test_missing_fields() {
+    let user: User = jsonlib::from_str("{\"userId\": 1, \"displayName\": \"x\"}").unwrap();
+    assert_eq!(user.email, None);
+    assert!(user.roles.is_empty());
+
+    let err = jsonlib::from_str::<User>("{\"displayName\": \"x\"}").unwrap_err();
+    assert_eq!(
+        err,
+        Error::MissingField {
+            field: "userId",
+            path: String::new(),
+        }
+    );
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Team {
+    name: String,
+    members: Vec<User>,
+}
+
+#[test]
+fn test_nested_error_path() {
+    let err = jsonlib::from_str::<Team>(
+        "{\"name\": \"a\", \"members\": [{\"userId\": 1, \"displayName\": \"x\"}, \
+         {\"userId\": -1, \"displayName\": \"y\"}]}",
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The value at $.members[1].userId is not a u64, it is an integer out of range!"
+    );
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Server {
+    host: String,
+    #[json(default = "default_port")]
+    port: u16,
+    #[json(flatten)]
+    limits: Limits,
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(rename_all = "kebab-case")]
+struct Limits {
+    max_connections: u32,
+    idle_timeout: Option<u32>,
+}
+
+#[test]
+fn test_flatten_and_default_path() {
+    let server: Server = jsonlib::from_str("{\"host\": \"h\", \"max-connections\": 10}").unwrap();
+    assert_eq!(
+        server,
+        Server {
+            host: "h".to_string(),
+            port: 8080,
+            limits: Limits {
+                max_connections: 10,
+                idle_timeout: None,
+            },
+        }
+    );
+    assert_eq!(
+        jsonlib::to_string(&server),
+        "{\"host\":\"h\",\"port\":8080,\"max-connections\":10,\"idle-timeout\":null}"
+    );
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Meters(f64);
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Point(i32, i32);
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Marker;
+
+#[test]
+fn test_tuple_and_unit_structs() {
+    assert_eq!(Meters(1.5).to_json(), json!(1.5));
+    assert_eq!(Point(1, -2).to_json(), json!([1, -2]));
+    assert_eq!(Marker.to_json(), json!(null));
+    assert_eq!(Point::from_json(&json!([3, 4])), Ok(Point(3, 4)));
+    assert_eq!(
+        Point::from_json(&json!([3])),
+        Err(Error::InvalidLength {
+            expected: 2,
+            found: 1,
+            path: String::new(),
+        })
+    );
+    assert_eq!(Marker::from_json(&json!(null)), Ok(Marker));
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(rename_all = "snake_case")]
+enum External {
+    Stop,
+    MoveTo(Point),
+    Resize(u32, u32),
+    #[json(rename = "paint")]
+    Paint {
+        color: String,
+    },
+}
+
+#[test]
+fn test_external_tagging() {
+    let cases = [
+        (External::Stop, json!("stop")),
+        (External::MoveTo(Point(1, 2)), json!({"move_to": [1, 2]})),
+        (External::Resize(3, 4), json!({"resize": [3, 4]})),
+        (
+            External::Paint {
+                color: "red".to_string(),
+            },
+            json!({"paint": {"color": "red"}}),
+        ),
+    ];
+    for (variant, value) in cases {
+        assert_eq!(variant.to_json(), value);
+        assert_eq!(External::from_json(&value), Ok(variant));
+    }
+    assert_eq!(
+        External::from_json(&json!("fly")),
+        Err(Error::UnknownVariant {
+            found: "fly".to_string(),
+            path: String::new(),
+        })
+    );
+    assert_eq!(
+        External::from_json(&json!({"paint": {"color": 1}}))
+            .unwrap_err()
+            .to_string(),
+        "The value at $.paint.color is not a string, it is a number!"
+    );
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(tag = "type")]
+enum Internal {
+    Ping,
+    Login { user: String },
+    Wrapped(Limits),
+}
+
+#[test]
+fn test_internal_tagging() {
+    let cases = [
+        (Internal::Ping, json!({"type": "Ping"})),
+        (
+            Internal::Login {
+                user: "mike".to_string(),
+            },
+            json!({"type": "Login", "user": "mike"}),
+        ),
+        (
+            Internal::Wrapped(Limits {
+                max_connections: 1,
+                idle_timeout: Some(2),
+            }),
+            json!({"type": "Wrapped", "max-connections": 1, "idle-timeout": 2}),
+        ),
+    ];
+    for (variant, value) in cases {
+        assert_eq!(variant.to_json(), value);
+        assert_eq!(Internal::from_json(&value), Ok(variant));
+    }
+    assert_eq!(
+        Internal::from_json(&json!({"user": "x"})),
+        Err(Error::MissingField {
+            field: "type",
+            path: String::new(),
+        })
+    );
+    assert_eq!(
+        Internal::from_json(&json!({"type": "Logout"}))
+            .unwrap_err()
+            .to_string(),
+        "Unknown variant \"Logout\" at $.type"
+    );
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(tag = "type")]
+enum InternalNewtype {
+    Name(String),
+}
+
+#[test]
+#[should_panic(
+    expected = "The internally tagged variant InternalNewtype::Name has to contain an object"
+)]
+fn test_internal_tagging_non_object() {
+    InternalNewtype::Name("bob".to_string()).to_json();
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(tag = "t", content = "c")]
+enum Adjacent {
+    Empty,
+    Count(u8),
+    Pair(bool, bool),
+}
+
+#[test]
+fn test_adjacent_tagging() {
+    let cases = [
+        (Adjacent::Empty, json!({"t": "Empty"})),
+        (Adjacent::Count(5), json!({"t": "Count", "c": 5})),
+        (
+            Adjacent::Pair(true, false),
+            json!({"t": "Pair", "c": [true, false]}),
+        ),
+    ];
+    for (variant, value) in cases {
+        assert_eq!(variant.to_json(), value);
+        assert_eq!(Adjacent::from_json(&value), Ok(variant));
+    }
+    assert_eq!(
+        Adjacent::from_json(&json!({"t": "Count", "c": 500}))
+            .unwrap_err()
+            .to_string(),
+        "The value at $.c is not a u8, it is an integer out of range!"
+    );
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+#[json(untagged)]
+enum Untagged {
+    Nothing,
+    Number(i64),
+    Text(String),
+    Named { id: u32 },
+}
+
+#[test]
+fn test_untagged() {
+    let cases = [
+        (Untagged::Nothing, json!(null)),
+        (Untagged::Number(-4), json!(-4)),
+        (Untagged::Text("x".to_string()), json!("x")),
+        (Untagged::Named { id: 9 }, json!({"id": 9})),
+    ];
+    for (variant, value) in cases {
+        assert_eq!(variant.to_json(), value);
+        assert_eq!(Untagged::from_json(&value), Ok(variant));
+    }
+    assert_eq!(
+        Untagged::from_json(&json!(true)).unwrap_err().to_string(),
+        "The value you are trying to extract is not a variant of Untagged, it is a bool!"
+    );
+}
+
+#[derive(Debug, PartialEq, ToJson, FromJson)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<Box<Page<T>>>,
+}
+
+#[test]
+fn test_generics() {
+    let page = Page {
+        items: vec!['a'],
+        next: Some(Box::new(Page {
+            items: vec!['b', 'c'],
+            next: None,
+        })),
+    };
+    let value = page.to_json();
+    assert_eq!(
+        value.to_string(),
+        "{\"items\":[\"a\"],\"next\":{\"items\":[\"b\",\"c\"],\"next\":null}}"
+    );
+    assert_eq!(Page::from_json(&value), Ok(page));
+}
@@ -0,0 +1,444 @@
+use crate::error::Error;
+use crate::map::Map;
+use crate::number::Number;
+use crate::value::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Types that can be turned into a `Value`.
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+/// Types that can be read back from a `Value`.
+///
+/// Errors for nested values carry the path to them, implementations for containers
+/// add their part with `Error::with_key` and `Error::with_index`.
+pub trait FromJson: Sized {
+    fn from_json(value: &Value) -> Result<Self, Error>;
+}
+
+impl ToJson for Value {
+    fn to_json(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl FromJson for Value {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        Ok(value.clone())
+    }
+}
+
+impl ToJson for Number {
+    fn to_json(&self) -> Value {
+        Value::Number(self.clone())
+    }
+}
+
+impl FromJson for Number {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        match value.as_number() {
+            Some(num) => Ok(num.clone()),
+            None => Err(value.type_error("a number")),
+        }
+    }
+}
+
+impl ToJson for Map {
+    fn to_json(&self) -> Value {
+        Value::Object(self.clone())
+    }
+}
+
+impl FromJson for Map {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        match value.as_object() {
+            Some(obj) => Ok(obj.clone()),
+            None => Err(value.type_error("an object")),
+        }
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        value.as_bool().ok_or_else(|| value.type_error("a bool"))
+    }
+}
+
+macro_rules! integer {
+    ($as:ident, $($ty:ident $expected:literal)*) => {
+        $(
+            impl ToJson for $ty {
+                fn to_json(&self) -> Value {
+                    Value::Number((*self).into())
+                }
+            }
+
+            impl FromJson for $ty {
+                fn from_json(value: &Value) -> Result<Self, Error> {
+                    let expected = $expected;
+                    let num = match value.as_number() {
+                        Some(num) => num,
+                        None => return Err(value.type_error(expected)),
+                    };
+                    match num.$as().and_then(|num| $ty::try_from(num).ok()) {
+                        Some(num) => Ok(num),
+                        None => Err(Error::TypeMismatch {
+                            expected,
+                            found: if num.is_integer() {
+                                "an integer out of range"
+                            } else {
+                                "a number with a fraction or exponent"
+                            },
+                            path: String::new(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer!(as_u64, u8 "a u8" u16 "a u16" u32 "a u32" u64 "a u64" usize "a usize");
+integer!(as_i64, i8 "an i8" i16 "an i16" i32 "an i32" i64 "an i64" isize "an isize");
+
+impl ToJson for f64 {
+    fn to_json(&self) -> Value {
+        Value::Number((*self).into())
+    }
+}
+
+/// Integers above 2^53 get rounded.
+impl FromJson for f64 {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        value.as_f64().ok_or_else(|| value.type_error("a number"))
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json(&self) -> Value {
+        Value::Number((*self).into())
+    }
+}
+
+/// Numbers outside the range of `f32` become infinity.
+impl FromJson for f32 {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        f64::from_json(value).map(|num| num as f32)
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        match value.as_str() {
+            Some(string) => Ok(string.to_string()),
+            None => Err(value.type_error("a string")),
+        }
+    }
+}
+
+impl ToJson for char {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+/// Reads a string with exactly one character.
+impl FromJson for char {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let expected = "a string of one character";
+        let string = value.as_str().ok_or_else(|| value.type_error(expected))?;
+        let mut chars = string.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(value.type_error(expected)),
+        }
+    }
+}
+
+impl ToJson for () {
+    fn to_json(&self) -> Value {
+        Value::Null
+    }
+}
+
+impl FromJson for () {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        match value {
+            Value::Null => Ok(()),
+            _ => Err(value.type_error("null")),
+        }
+    }
+}
+
+/// `None` is written as `null`.
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Value {
+        match self {
+            Some(inner) => inner.to_json(),
+            None => Value::Null,
+        }
+    }
+}
+
+/// `null` is read as `None`.
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        match value {
+            Value::Null => Ok(None),
+            _ => T::from_json(value).map(Some),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Value {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let arr = value
+            .as_array()
+            .ok_or_else(|| value.type_error("an array"))?;
+        arr.iter()
+            .enumerate()
+            .map(|(i, elem)| T::from_json(elem).map_err(|e| e.with_index(i)))
+            .collect()
+    }
+}
+
+impl<T: ToJson, const N: usize> ToJson for [T; N] {
+    fn to_json(&self) -> Value {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson, const N: usize> FromJson for [T; N] {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let vec = Vec::<T>::from_json(value)?;
+        vec.try_into().map_err(|vec: Vec<T>| Error::InvalidLength {
+            expected: N,
+            found: vec.len(),
+            path: String::new(),
+        })
+    }
+}
+
+macro_rules! tuple {
+    ($len:expr => $($name:ident $index:tt)+) => {
+        impl<$($name: ToJson),+> ToJson for ($($name,)+) {
+            fn to_json(&self) -> Value {
+                Value::Array(vec![$(self.$index.to_json()),+])
+            }
+        }
+
+        /// Reads an array with exactly as many elements as the tuple.
+        impl<$($name: FromJson),+> FromJson for ($($name,)+) {
+            fn from_json(value: &Value) -> Result<Self, Error> {
+                let arr = value
+                    .as_array()
+                    .ok_or_else(|| value.type_error("an array"))?;
+                if arr.len() != $len {
+                    return Err(Error::InvalidLength {
+                        expected: $len,
+                        found: arr.len(),
+                        path: String::new(),
+                    });
+                }
+                Ok(($($name::from_json(&arr[$index]).map_err(|e| e.with_index($index))?,)+))
+            }
+        }
+    };
+}
+
+tuple!(1 => A 0);
+tuple!(2 => A 0 B 1);
+tuple!(3 => A 0 B 1 C 2);
+tuple!(4 => A 0 B 1 C 2 D 3);
+tuple!(5 => A 0 B 1 C 2 D 3 E 4);
+tuple!(6 => A 0 B 1 C 2 D 3 E 4 F 5);
+tuple!(7 => A 0 B 1 C 2 D 3 E 4 F 5 G 6);
+tuple!(8 => A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7);
+
+/// The members end up in the order the `HashMap` iterates them, which is arbitrary.
+impl<K: AsRef<str>, V: ToJson, S> ToJson for HashMap<K, V, S> {
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.iter()
+                .map(|(key, value)| (key.as_ref(), value.to_json()))
+                .collect(),
+        )
+    }
+}
+
+impl<K, V, S> FromJson for HashMap<K, V, S>
+where
+    K: From<String> + Eq + Hash,
+    V: FromJson,
+    S: BuildHasher + Default,
+{
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let obj = value
+            .as_object()
+            .ok_or_else(|| value.type_error("an object"))?;
+        obj.iter()
+            .map(|(key, value)| match V::from_json(value) {
+                Ok(value) => Ok((K::from(key.clone()), value)),
+                Err(e) => Err(e.with_key(key)),
+            })
+            .collect()
+    }
+}
+
+impl<K: AsRef<str>, V: ToJson> ToJson for BTreeMap<K, V> {
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.iter()
+                .map(|(key, value)| (key.as_ref(), value.to_json()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: From<String> + Ord, V: FromJson> FromJson for BTreeMap<K, V> {
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let obj = value
+            .as_object()
+            .ok_or_else(|| value.type_error("an object"))?;
+        obj.iter()
+            .map(|(key, value)| match V::from_json(value) {
+                Ok(value) => Ok((K::from(key.clone()), value)),
+                Err(e) => Err(e.with_key(key)),
+            })
+            .collect()
+    }
+}
+
+macro_rules! pointer {
+    ($($ty:ident)*) => {
+        $(
+            impl<T: ToJson + ?Sized> ToJson for $ty<T> {
+                fn to_json(&self) -> Value {
+                    (**self).to_json()
+                }
+            }
+
+            impl<T: FromJson> FromJson for $ty<T> {
+                fn from_json(value: &Value) -> Result<Self, Error> {
+                    T::from_json(value).map($ty::new)
+                }
+            }
+        )*
+    };
+}
+
+pointer!(Box Rc Arc);
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Value {
+        (**self).to_json()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(u8::from_json(&json!(255)), Ok(255));
+        assert_eq!(i64::from_json(&json!(-7)), Ok(-7));
+        assert_eq!(f64::from_json(&json!(1)), Ok(1.0));
+        assert_eq!(char::from_json(&json!("é")), Ok('é'));
+        assert_eq!(Option::<bool>::from_json(&json!(null)), Ok(None));
+        assert_eq!(String::from_json(&json!("x")), Ok("x".to_string()));
+        assert_eq!(Box::<u16>::from_json(&json!(3)), Ok(Box::new(3)));
+        assert_eq!(<()>::from_json(&json!(null)), Ok(()));
+
+        assert_eq!(
+            u8::from_json(&json!(256)).unwrap_err().to_string(),
+            "The value you are trying to extract is not a u8, it is an integer out of range!"
+        );
+        assert_eq!(
+            u32::from_json(&json!(-1)).unwrap_err().to_string(),
+            "The value you are trying to extract is not a u32, it is an integer out of range!"
+        );
+        assert_eq!(
+            i8::from_json(&json!(128)).unwrap_err().to_string(),
+            "The value you are trying to extract is not an i8, it is an integer out of range!"
+        );
+        assert_eq!(
+            i32::from_json(&json!(1.5)).unwrap_err().to_string(),
+            "The value you are trying to extract is not an i32, it is a number with a fraction or exponent!"
+        );
+        assert!(char::from_json(&json!("ab")).is_err());
+    }
+
+    #[test]
+    fn test_containers_round_trip() {
+        let mut scores = BTreeMap::new();
+        scores.insert("alice".to_string(), vec![(1u8, Some(true))]);
+        scores.insert("bob".to_string(), vec![(2, None), (3, Some(false))]);
+        let value = scores.to_json();
+        assert_eq!(
+            value.to_string(),
+            "{\"alice\":[[1,true]],\"bob\":[[2,null],[3,false]]}"
+        );
+        assert_eq!(BTreeMap::from_json(&value), Ok(scores));
+
+        let point = [1.5, -2.0, 0.0];
+        assert_eq!(<[f64; 3]>::from_json(&point.to_json()), Ok(point));
+        let names: HashMap<String, Arc<str>> = HashMap::new();
+        assert_eq!(names.to_json(), json!({}));
+    }
+
+    #[test]
+    fn test_error_paths() {
+        let value = json!({"users": [{"age": 3}, {"id": 4, "age": "old"}]});
+        let err = HashMap::<String, Vec<HashMap<String, u32>>>::from_json(&value).unwrap_err();
+        assert_eq!(
+            err,
+            Error::TypeMismatch {
+                expected: "a u32",
+                found: "a string",
+                path: ".users[1].age".to_string(),
+            }
+        );
+
+        let err = Vec::<(i8, i8)>::from_json(&json!([[1, 2], [3]])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected an array of 2 elements, got 1 elements at $[1]"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use crate::serializer;
+use std::{fmt, io};
+
+/// A location in the parsed text. Lines and columns start at 1 and count characters,
+/// the offset starts at 0 and counts bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// A token that is not allowed where it was found, e.g. a missing comma
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+        position: Position,
+    },
+    /// The input ended in the middle of a value
+    UnexpectedEof {
+        expected: &'static str,
+        position: Position,
+    },
+    /// A character that can not start or continue a token
+    UnexpectedCharacter {
+        found: char,
+        position: Position,
+    },
+    InvalidNumber {
+        position: Position,
+    },
+    InvalidEscape {
+        position: Position,
+    },
+    /// A raw character below U+0020 inside a string
+    ControlCharacter {
+        position: Position,
+    },
+    /// The input ended before the string that starts at `position` was closed
+    UnterminatedString {
+        position: Position,
+    },
+    InvalidUtf8 {
+        position: Position,
+    },
+    /// Non-whitespace content after the end of the root value
+    TrailingCharacters {
+        position: Position,
+    },
+    DepthLimitExceeded {
+        limit: usize,
+        position: Position,
+    },
+    /// A `Value` was extracted as a type it does not hold. `path` leads from the root
+    /// to the value, e.g. `.tags[2]`, and is empty for the root itself
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+        path: String,
+    },
+    /// An array converted into a fixed size type, like a tuple, has the wrong length
+    InvalidLength {
+        expected: usize,
+        found: usize,
+        path: String,
+    },
+    /// An object converted into a struct lacks a field that has no default
+    MissingField {
+        field: &'static str,
+        path: String,
+    },
+    /// The name of an enum variant that the enum does not have
+    UnknownVariant {
+        found: String,
+        path: String,
+    },
+    /// A JSON Pointer that is malformed or can not be followed, `reason` says why
+    InvalidPointer {
+        pointer: String,
+        reason: &'static str,
+    },
+    /// Operation `index` of a JSON Patch could not be applied at `pointer`
+    PatchFailed {
+        index: usize,
+        pointer: String,
+        reason: &'static str,
+    },
+    /// A JSONPath expression that does not follow the grammar of RFC 9535
+    InvalidPath {
+        reason: &'static str,
+        position: Position,
+    },
+    /// Reading the input from an `io::Read` failed
+    Io {
+        kind: io::ErrorKind,
+        message: String,
+        position: Position,
+    },
+    /// Any other error, e.g. one reported by a serde implementation
+    Custom {
+        message: String,
+        position: Option<Position>,
+    },
+}
+
+impl Error {
+    /// Where in the input the error happened, `None` for errors that do not come from parsing.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Error::UnexpectedToken { position, .. }
+            | Error::UnexpectedEof { position, .. }
+            | Error::UnexpectedCharacter { position, .. }
+            | Error::InvalidNumber { position }
+            | Error::InvalidEscape { position }
+            | Error::ControlCharacter { position }
+            | Error::UnterminatedString { position }
+            | Error::InvalidUtf8 { position }
+            | Error::TrailingCharacters { position }
+            | Error::DepthLimitExceeded { position, .. }
+            | Error::InvalidPath { position, .. }
+            | Error::Io { position, .. } => Some(*position),
+            Error::Custom { position, .. } => *position,
+            Error::TypeMismatch { .. }
+            | Error::InvalidLength { .. }
+            | Error::MissingField { .. }
+            | Error::UnknownVariant { .. }
+            | Error::InvalidPointer { .. }
+            | Error::PatchFailed { .. } => None,
+        }
+    }
+
+    /// Prefixes the path of a conversion error with an object key, for `FromJson`
+    /// implementations that convert the members of an object.
+    pub fn with_key(mut self, key: &str) -> Error {
+        if let Some(path) = self.path_mut() {
+            let mut segment = String::new();
+            if is_identifier(key) {
+                segment.push('.');
+                segment.push_str(key);
+            } else {
+                // Writing into a String never fails
+                segment.push('[');
+                serializer::write_string(key, &mut segment).unwrap();
+                segment.push(']');
+            }
+            path.insert_str(0, &segment);
+        }
+        self
+    }
+
+    /// Prefixes the path of a conversion error with an array index, for `FromJson`
+    /// implementations that convert the elements of an array.
+    pub fn with_index(mut self, index: usize) -> Error {
+        if let Some(path) = self.path_mut() {
+            path.insert_str(0, &format!("[{}]", index));
+        }
+        self
+    }
+
+    fn path_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Error::TypeMismatch { path, .. }
+            | Error::InvalidLength { path, .. }
+            | Error::MissingField { path, .. }
+            | Error::UnknownVariant { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// A short guess at what is wrong with the input, for error reports.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Error::UnexpectedToken {
+                expected, found, ..
+            } => match (*expected, found.as_str()) {
+                ("',' or ']'", _) => Some("missing comma between array elements?"),
+                ("',' or '}'", _) => Some("missing comma between object members?"),
+                ("a colon (':')", _) => Some("missing ':' between the key and the value?"),
+                ("a key", "'}'") | ("a value", "']'") => Some("trailing comma before the end?"),
+                ("a key", _) => Some("object keys have to be strings in double quotes"),
+                _ => None,
+            },
+            Error::UnexpectedEof { .. } => {
+                Some("the input ended early, is a closing bracket missing?")
+            }
+            Error::UnexpectedCharacter { found: '\'', .. } => {
+                Some("strings have to be in double quotes")
+            }
+            Error::UnexpectedCharacter { found, .. } if found.is_alphabetic() => {
+                Some("unquoted string? Only true, false and null are written without quotes")
+            }
+            Error::UnexpectedCharacter { .. } => None,
+            Error::InvalidNumber { .. } => {
+                Some("numbers look like -12.5e3, without leading zeros or a trailing '.'")
+            }
+            Error::InvalidEscape { .. } => {
+                Some("valid escapes are \\\" \\\\ \\/ \\b \\f \\n \\r \\t and \\uXXXX")
+            }
+            Error::ControlCharacter { .. } => {
+                Some("tabs and line breaks inside strings have to be escaped")
+            }
+            Error::UnterminatedString { .. } => Some("the string is never closed, missing '\"'?"),
+            Error::InvalidUtf8 { .. } => Some("the input is not UTF-8 encoded"),
+            Error::TrailingCharacters { .. } => {
+                Some("only one value is allowed at the top level, missing brackets?")
+            }
+            Error::DepthLimitExceeded { .. } => {
+                Some("ParseOptions::max_depth can be raised to allow deeper nesting")
+            }
+            Error::TypeMismatch { .. }
+            | Error::InvalidLength { .. }
+            | Error::MissingField { .. }
+            | Error::UnknownVariant { .. }
+            | Error::InvalidPointer { .. }
+            | Error::PatchFailed { .. }
+            | Error::InvalidPath { .. }
+            | Error::Io { .. }
+            | Error::Custom { .. } => None,
+        }
+    }
+
+    /// Formats the error with the offending line of `source`, a caret under the column
+    /// and a hint, e.g.
+    ///
+    /// ```text
+    /// error: Expected ',' or ']', got the number 2 at line 1, column 4
+    ///   |
+    /// 1 | [1 2]
+    ///   |    ^ missing comma between array elements?
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let mut report = format!("error: {}", self);
+        // Lines start at 1, so a default position points at no line
+        let position = match self.position() {
+            Some(position) if position.line > 0 => position,
+            _ => return report,
+        };
+        // The input can end with a line break, leaving the position on an empty last line
+        let line = source.lines().nth(position.line - 1).unwrap_or_default();
+        let gutter = " ".repeat(position.line.to_string().len());
+        // Keep tabs so the caret lines up with the text above it
+        let indent: String = line
+            .chars()
+            .take(position.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        report.push_str(&format!(
+            "\n{} |\n{} | {}\n{} | {}^",
+            gutter, position.line, line, gutter, indent
+        ));
+        if let Some(hint) = self.hint() {
+            report.push(' ');
+            report.push_str(hint);
+        }
+        report
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnexpectedToken {
+                expected,
+                found,
+                position,
+            } => write!(f, "Expected {}, got {} at {}", expected, found, position),
+            Error::UnexpectedEof { expected, position } => {
+                write!(f, "Expected {}, got end of input at {}", expected, position)
+            }
+            Error::UnexpectedCharacter { found, position } => {
+                write!(f, "Unexpected character {:?} at {}", found, position)
+            }
+            Error::InvalidNumber { position } => write!(f, "Invalid number at {}", position),
+            Error::InvalidEscape { position } => {
+                write!(f, "Invalid escape sequence at {}", position)
+            }
+            Error::ControlCharacter { position } => {
+                write!(f, "Unescaped control character in a string at {}", position)
+            }
+            Error::UnterminatedString { position } => {
+                write!(f, "Unterminated string started at {}", position)
+            }
+            Error::InvalidUtf8 { position } => write!(
+                f,
+                "Invalid UTF-8 at byte offset {} ({})",
+                position.offset, position
+            ),
+            Error::TrailingCharacters { position } => {
+                write!(f, "Unexpected content after the value at {}", position)
+            }
+            Error::DepthLimitExceeded { limit, position } => write!(
+                f,
+                "Nesting is deeper than the limit of {} at {}",
+                limit, position
+            ),
+            Error::TypeMismatch {
+                expected,
+                found,
+                path,
+            } if path.is_empty() => write!(
+                f,
+                "The value you are trying to extract is not {}, it is {}!",
+                expected, found
+            ),
+            Error::TypeMismatch {
+                expected,
+                found,
+                path,
+            } => write!(
+                f,
+                "The value at ${} is not {}, it is {}!",
+                path, expected, found
+            ),
+            Error::InvalidLength {
+                expected,
+                found,
+                path,
+            } => write!(
+                f,
+                "Expected an array of {} elements, got {} elements at ${}",
+                expected, found, path
+            ),
+            Error::MissingField { field, path } => {
+                write!(f, "Missing field {:?} in the object at ${}", field, path)
+            }
+            Error::UnknownVariant { found, path } => {
+                write!(f, "Unknown variant {:?} at ${}", found, path)
+            }
+            Error::InvalidPointer { pointer, reason } => {
+                write!(f, "The JSON Pointer {:?} {}", pointer, reason)
+            }
+            Error::PatchFailed {
+                index,
+                pointer,
+                reason,
+            } => write!(
+                f,
+                "JSON Patch operation {} failed at {:?}: {}",
+                index, pointer, reason
+            ),
+            Error::InvalidPath { reason, position } => {
+                write!(f, "Invalid JSONPath, {} at {}", reason, position)
+            }
+            Error::Io {
+                message, position, ..
+            } => write!(
+                f,
+                "Reading the input failed at byte offset {} ({}): {}",
+                position.offset, position, message
+            ),
+            Error::Custom {
+                message,
+                position: Some(position),
+            } => write!(f, "{} at {}", message, position),
+            Error::Custom {
+                message,
+                position: None,
+            } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Keys that can be written as `.key` in a path, the rest are written as `["key"]`.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_missing_comma() {
+        let source = "{\n\t\"list\": [1 2]\n}";
+        let err = crate::parse(source).unwrap_err();
+        assert_eq!(
+            err.render(source),
+            "error: Expected ',' or ']', got the number 2 at line 2, column 13\n  \
+             |\n\
+             2 | \t\"list\": [1 2]\n  \
+             | \t           ^ missing comma between array elements?"
+        );
+    }
+
+    #[test]
+    fn test_render_unterminated_string() {
+        let source = "[\n\n  \"open";
+        let err = crate::parse(source).unwrap_err();
+        assert_eq!(
+            err.render(source),
+            "error: Unterminated string started at line 3, column 3\n  \
+             |\n\
+             3 |   \"open\n  \
+             |   ^ the string is never closed, missing '\"'?"
+        );
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let source = "[1,\n";
+        let err = crate::parse(source).unwrap_err();
+        assert_eq!(
+            err.render(source),
+            "error: Expected a value, got end of input at line 2, column 1\n  \
+             |\n\
+             2 | \n  \
+             | ^ the input ended early, is a closing bracket missing?"
+        );
+    }
+
+    #[test]
+    fn test_render_without_position() {
+        let err = Error::TypeMismatch {
+            expected: "a string",
+            found: "a number",
+            path: String::new(),
+        };
+        assert_eq!(
+            err.render("1"),
+            "error: The value you are trying to extract is not a string, it is a number!"
+        );
+    }
+
+    #[test]
+    fn test_render_zero_position() {
+        let err = Error::Custom {
+            message: "bad".to_string(),
+            position: Some(Position::default()),
+        };
+        assert_eq!(err.render("abc"), format!("error: {}", err));
+        let err = Error::Custom {
+            message: "bad".to_string(),
+            position: Some(Position {
+                line: 1,
+                column: 0,
+                offset: 0,
+            }),
+        };
+        assert!(err.render("abc").ends_with("1 | abc\n  | ^"));
+    }
+
+    #[test]
+    fn test_path_segments() {
+        let err = Error::TypeMismatch {
+            expected: "a bool",
+            found: "null",
+            path: String::new(),
+        }
+        .with_key("on")
+        .with_index(3)
+        .with_key("a b")
+        .with_key("lights");
+        assert_eq!(
+            err.to_string(),
+            "The value at $.lights[\"a b\"][3].on is not a bool, it is null!"
+        );
+    }
+}
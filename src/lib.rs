@@ -1,13 +1,120 @@
+mod convert;
+mod error;
+mod macros;
+mod map;
+mod merge_patch;
+mod number;
+pub mod options;
 mod parser;
-mod tokenizer;
+pub mod patch;
+#[cfg(feature = "jsonpath")]
+pub mod path;
+mod pointer;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod serializer;
+pub mod stream;
+pub mod tokenizer;
 pub mod value;
 
+pub use convert::{FromJson, ToJson};
+pub use error::{Error, Position};
+#[cfg(feature = "derive")]
+pub use jsonlib_derive::{FromJson, ToJson};
+pub use map::Map;
+pub use number::Number;
+use options::{DiffOptions, ParseOptions};
 use parser::Parser;
-use std::error::Error;
+pub use serializer::pretty_config::{Newline, PrettyConfig};
+use std::io;
 use value::Value;
 
-pub fn parse(input: &str) -> Result<Value, Box<dyn Error>> {
+pub fn parse(input: &str) -> Result<Value, Error> {
     let mut parser = Parser::new();
     parser.read_into_stream(input);
-    parser.parse_obj()
+    parser.parse()
+}
+
+pub fn parse_with_options(input: &str, options: ParseOptions) -> Result<Value, Error> {
+    let mut parser = Parser::with_options(options);
+    parser.read_into_stream(input);
+    parser.parse()
+}
+
+/// Parses raw bytes, rejecting input that is not valid UTF-8.
+pub fn parse_bytes(input: &[u8]) -> Result<Value, Error> {
+    let input = std::str::from_utf8(input).map_err(|e| {
+        // Everything before the error is valid, so it can be used to find the line and column
+        let valid = std::str::from_utf8(&input[..e.valid_up_to()]).unwrap();
+        let line_start = valid.rfind('\n').map_or(0, |i| i + 1);
+        Error::InvalidUtf8 {
+            position: Position {
+                line: valid.matches('\n').count() + 1,
+                column: valid[line_start..].chars().count() + 1,
+                offset: e.valid_up_to(),
+            },
+        }
+    })?;
+    parse(input)
+}
+
+/// Parses `input` and converts the value into `T`.
+pub fn from_str<T: FromJson>(input: &str) -> Result<T, Error> {
+    T::from_json(&parse(input)?)
+}
+
+/// Parses the JSON text read from `reader` a chunk at a time, without reading all of it
+/// into memory first. The reader does not need to be buffered. Errors, including those of
+/// the reader itself, have the byte offset in the stream in their position.
+pub fn parse_reader<R: io::Read>(reader: R) -> Result<Value, Error> {
+    let mut reader = stream::Reader::from_reader(reader);
+    // At the start there is always a value or an error
+    let value = reader.read_value()?.unwrap();
+    // After the value this only checks that nothing else follows
+    reader.next_event()?;
+    Ok(value)
+}
+
+/// Parses the JSON text read from `reader` like `parse_reader` and converts the value into `T`.
+pub fn from_reader<T: FromJson, R: io::Read>(reader: R) -> Result<T, Error> {
+    T::from_json(&parse_reader(reader)?)
+}
+
+/// Converts `value` into compact JSON text.
+pub fn to_string<T: ToJson + ?Sized>(value: &T) -> String {
+    value.to_json().to_string()
+}
+
+/// Writes `value` as compact JSON text into `writer`.
+pub fn to_writer<W: io::Write>(value: &Value, mut writer: W) -> io::Result<()> {
+    write!(writer, "{}", value)
+}
+
+/// Writes `value` as JSON text laid out over multiple lines as described by `config`.
+pub fn to_string_pretty(value: &Value, config: &PrettyConfig) -> String {
+    let mut out = String::new();
+    // Writing into a String never fails
+    serializer::write_pretty(value, config, 0, &mut out).unwrap();
+    if config.trailing_newline {
+        out.push_str(config.newline.as_str());
+    }
+    out
+}
+
+/// An RFC 6902 patch that turns `from` into `to` when given to `patch::apply`. Arrays are
+/// compared index by index, see `diff_with_options` for other ways.
+pub fn diff(from: &Value, to: &Value) -> Value {
+    diff_with_options(from, to, DiffOptions::default())
+}
+
+/// Like `diff`, with the ways of comparing arrays and finding moves set in `options`.
+pub fn diff_with_options(from: &Value, to: &Value, options: DiffOptions) -> Value {
+    patch::diff(from, to, options)
+}
+
+/// The smallest RFC 7396 merge patch that turns `from` into `to` when given to
+/// `Value::merge_patch`. Merge patches can not set an object member to `null`, those
+/// members are left out of `to` after merging.
+pub fn merge_patch_diff(from: &Value, to: &Value) -> Value {
+    merge_patch::diff(from, to)
 }
@@ -0,0 +1,162 @@
+/// Builds a `Value` from JSON-like syntax. Keys are string literals or Rust expressions in
+/// parentheses, values can be JSON literals, nested arrays and objects, or any Rust expression
+/// that converts `Into<Value>`.
+///
+/// ```
+/// use jsonlib::json;
+///
+/// let id = 7;
+/// let tags = vec!["a", "b"];
+/// let value = json!({
+///     "id": id,
+///     "tags": tags,
+///     "owner": {"name": "Mike", "admin": false},
+///     "scores": [1.5, id * 2, null],
+///     "note": null
+/// });
+/// assert_eq!(value["owner"]["name"].as_str(), Some("Mike"));
+/// ```
+#[macro_export]
+macro_rules! json {
+    // Arrays are munched one element at a time, because `null`, `[..]` and `{..}` are
+    // not expressions that `Into<Value>` understands
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        vec![$($elems),*]
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!(null)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!(true)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!(false)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!({$($object)*})] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)* $crate::json!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json!(@array [$($elems,)* $crate::json!($last)])
+    };
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // Objects insert one member at a time into `$object`
+    (@object $object:ident) => {};
+    (@object $object:ident $key:tt : null $(, $($rest:tt)*)?) => {
+        $object.insert($key, $crate::json!(null));
+        $crate::json!(@object $object $($($rest)*)?);
+    };
+    (@object $object:ident $key:tt : true $(, $($rest:tt)*)?) => {
+        $object.insert($key, $crate::json!(true));
+        $crate::json!(@object $object $($($rest)*)?);
+    };
+    (@object $object:ident $key:tt : false $(, $($rest:tt)*)?) => {
+        $object.insert($key, $crate::json!(false));
+        $crate::json!(@object $object $($($rest)*)?);
+    };
+    (@object $object:ident $key:tt : [$($array:tt)*] $(, $($rest:tt)*)?) => {
+        $object.insert($key, $crate::json!([$($array)*]));
+        $crate::json!(@object $object $($($rest)*)?);
+    };
+    (@object $object:ident $key:tt : {$($inner:tt)*} $(, $($rest:tt)*)?) => {
+        $object.insert($key, $crate::json!({$($inner)*}));
+        $crate::json!(@object $object $($($rest)*)?);
+    };
+    (@object $object:ident $key:tt : $value:expr, $($rest:tt)*) => {
+        $object.insert($key, $crate::json!($value));
+        $crate::json!(@object $object $($rest)*);
+    };
+    (@object $object:ident $key:tt : $value:expr) => {
+        $object.insert($key, $crate::json!($value));
+    };
+
+    (null) => {
+        $crate::value::Value::Null
+    };
+    (true) => {
+        $crate::value::Value::Bool(true)
+    };
+    (false) => {
+        $crate::value::Value::Bool(false)
+    };
+    ([]) => {
+        $crate::value::Value::Array(vec![])
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::value::Value::Array($crate::json!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::value::Value::Object($crate::Map::new())
+    };
+    ({ $($tt:tt)+ }) => {{
+        let mut object = $crate::Map::new();
+        $crate::json!(@object object $($tt)+);
+        $crate::value::Value::Object(object)
+    }};
+    ($other:expr) => {
+        $crate::value::Value::from($other)
+    };
+}
+
+#[cfg(test)]
+pub mod test {
+    use crate::value::Value;
+    use crate::Map;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(json!(null), Value::Null);
+        assert_eq!(json!(true), Value::Bool(true));
+        assert_eq!(json!("hi"), Value::String("hi".to_string()));
+        assert_eq!(json!(-3), Value::Number((-3).into()));
+        assert_eq!(json!(2.5), Value::Number(2.5.into()));
+        assert_eq!(json!([]), Value::Array(vec![]));
+        assert_eq!(json!({}), Value::Object(Map::new()));
+    }
+
+    #[test]
+    fn test_nested() {
+        let id = 7u64;
+        let (a, b) = ("x", "y".to_string());
+        let value = json!({
+            "id": id,
+            "tags": [a, b, null, [true, {}], id + 1],
+            "ok": true,
+            "owner": {"name": "Mike", "nick": None::<String>},
+            "x": null,
+        });
+        assert_eq!(
+            value.to_string(),
+            "{\"id\":7,\"tags\":[\"x\",\"y\",null,[true,{}],8],\"ok\":true,\
+             \"owner\":{\"name\":\"Mike\",\"nick\":null},\"x\":null}"
+        );
+    }
+
+    #[test]
+    fn test_expression_keys_and_values() {
+        let key = "dynamic";
+        let scores: HashMap<&str, i32> = [("a", 1)].into_iter().collect();
+        let value = json!({
+            (key): vec![1, 2],
+            "scores": scores,
+            "sum": [1, 2].iter().sum::<i32>(),
+            "nested": json!([false]),
+        });
+        assert_eq!(
+            value.to_string(),
+            "{\"dynamic\":[1,2],\"scores\":{\"a\":1},\"sum\":3,\"nested\":[false]}"
+        );
+    }
+}
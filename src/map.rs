@@ -0,0 +1,293 @@
+use crate::value::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::{slice, vec};
+
+/// The members of a JSON object, kept in the order they were inserted.
+///
+/// Parsing keeps the order keys had in the input, so a document can be edited
+/// and written back without reshuffling it.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, Value)>,
+    /// Position of every key in `entries`
+    indices: HashMap<String, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map {
+            entries: Vec::with_capacity(capacity),
+            indices: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let index = *self.indices.get(key)?;
+        Some(&self.entries[index].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        let index = *self.indices.get(key)?;
+        Some(&mut self.entries[index].1)
+    }
+
+    /// The member at `index` in insertion order.
+    pub fn get_index(&self, index: usize) -> Option<(&String, &Value)> {
+        self.entries.get(index).map(|(key, value)| (key, value))
+    }
+
+    /// The stored key along with its value.
+    pub fn get_key_value(&self, key: &str) -> Option<(&String, &Value)> {
+        let index = *self.indices.get(key)?;
+        self.get_index(index)
+    }
+
+    /// Inserts a member at the end, or replaces the value of an existing key in place.
+    /// Returns the value that was replaced.
+    pub fn insert(&mut self, key: impl Into<String>, value: Value) -> Option<Value> {
+        let key = key.into();
+        match self.indices.get(&key) {
+            Some(&index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes a member, keeping the order of the rest. Same as `shift_remove`.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.shift_remove(key)
+    }
+
+    /// Removes a member and shifts the ones after it back, keeping their order.
+    /// Takes time proportional to the number of members.
+    pub fn shift_remove(&mut self, key: &str) -> Option<Value> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(index);
+        for (key, _) in &self.entries[index..] {
+            // Every key in entries is in indices
+            *self.indices.get_mut(key).unwrap() -= 1;
+        }
+        Some(value)
+    }
+
+    /// Removes a member by moving the last one into its place. Faster than
+    /// `shift_remove`, but changes the order.
+    pub fn swap_remove(&mut self, key: &str) -> Option<Value> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.swap_remove(index);
+        if let Some((moved, _)) = self.entries.get(index) {
+            self.indices.insert(moved.clone(), index);
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+}
+
+/// Maps are equal when they have the same members, regardless of their order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Panics if the key is not in the map, like `HashMap` does.
+impl Index<&str> for Map {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        match self.get(key) {
+            Some(value) => value,
+            None => panic!("Key {:?} is not in the map", key),
+        }
+    }
+}
+
+impl IndexMut<&str> for Map {
+    fn index_mut(&mut self, key: &str) -> &mut Value {
+        match self.get_mut(key) {
+            Some(value) => value,
+            None => panic!("Key {:?} is not in the map", key),
+        }
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (K, Value)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Into<String>> Extend<(K, Value)> for Map {
+    fn extend<I: IntoIterator<Item = (K, Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+pub struct Iter<'a>(slice::Iter<'a, (String, Value)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+pub struct IterMut<'a>(slice::IterMut<'a, (String, Value)>);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a String, &'a mut Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a String, &'a mut Value);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (String, Value);
+    type IntoIter = vec::IntoIter<(String, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    fn keys(map: &Map) -> Vec<&str> {
+        map.keys().map(|key| key.as_str()).collect()
+    }
+
+    fn sample() -> Map {
+        ["c", "a", "d", "b"]
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (*key, Value::Number(i.into())))
+            .collect()
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let mut map = sample();
+        assert_eq!(keys(&map), vec!["c", "a", "d", "b"]);
+        assert_eq!(map.insert("a", Value::Null), Some(Value::Number(1.into())));
+        assert_eq!(keys(&map), vec!["c", "a", "d", "b"]);
+        assert_eq!(map["a"], Value::Null);
+        assert_eq!(map.get("x"), None);
+    }
+
+    #[test]
+    fn test_shift_remove() {
+        let mut map = sample();
+        assert_eq!(map.shift_remove("a"), Some(Value::Number(1.into())));
+        assert_eq!(map.remove("x"), None);
+        assert_eq!(keys(&map), vec!["c", "d", "b"]);
+        assert_eq!(map["b"], Value::Number(3.into()));
+        assert_eq!(
+            map.get_index(1),
+            Some((&"d".to_string(), &Value::Number(2.into())))
+        );
+    }
+
+    #[test]
+    fn test_swap_remove() {
+        let mut map = sample();
+        assert_eq!(map.swap_remove("c"), Some(Value::Number(0.into())));
+        assert_eq!(keys(&map), vec!["b", "a", "d"]);
+        assert_eq!(map["b"], Value::Number(3.into()));
+        assert_eq!(map.swap_remove("d"), Some(Value::Number(2.into())));
+        assert_eq!(keys(&map), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_equality_ignores_order() {
+        let mut reversed: Map = sample().into_iter().rev().collect();
+        assert_eq!(reversed, sample());
+        reversed["a"] = Value::Null;
+        assert_ne!(reversed, sample());
+    }
+}
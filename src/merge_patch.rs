@@ -0,0 +1,162 @@
+use crate::map::Map;
+use crate::value::Value;
+
+impl Value {
+    /// Applies an RFC 7396 JSON Merge Patch. Members of an object patch are merged into
+    /// the value, with `null` removing a member, and any other patch replaces the value:
+    /// `{"a": {"b": 1}, "c": null}` sets `a.b` to 1 and removes `c`, keeping the rest.
+    pub fn merge_patch(&mut self, patch: &Value) {
+        let members = match patch {
+            Value::Object(members) => members,
+            _ => {
+                *self = patch.clone();
+                return;
+            }
+        };
+        if !matches!(self, Value::Object(_)) {
+            *self = Value::Object(Map::new());
+        }
+        let obj = self.as_object_mut().unwrap();
+        for (key, value) in members.iter() {
+            if value.is_null() {
+                obj.shift_remove(key);
+                continue;
+            }
+            match obj.get_mut(key) {
+                Some(target) => target.merge_patch(value),
+                None => {
+                    let mut target = Value::Null;
+                    target.merge_patch(value);
+                    obj.insert(key.as_str(), target);
+                }
+            }
+        }
+    }
+}
+
+/// The smallest merge patch that turns `from` into `to`, see `merge_patch_diff`.
+pub(crate) fn diff(from: &Value, to: &Value) -> Value {
+    let (from, to) = match (from, to) {
+        (Value::Object(from), Value::Object(to)) => (from, to),
+        // Only objects are merged, everything else has to be replaced
+        _ => return to.clone(),
+    };
+    let mut patch = Map::new();
+    for (key, _) in from.iter().filter(|(key, _)| !to.contains_key(key)) {
+        patch.insert(key.as_str(), Value::Null);
+    }
+    for (key, value) in to.iter() {
+        match from.get(key) {
+            Some(old @ Value::Object(_)) if matches!(value, Value::Object(_)) => {
+                let members = diff(old, value);
+                if members
+                    .as_object()
+                    .is_some_and(|members| !members.is_empty())
+                {
+                    patch.insert(key.as_str(), members);
+                }
+            }
+            Some(old) if old.json_eq(value) => {}
+            _ => {
+                patch.insert(key.as_str(), value.clone());
+            }
+        }
+    }
+    Value::Object(patch)
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn test_rfc_examples() {
+        for (target, patch, result) in [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (
+                json!({"a": "b", "b": "c"}),
+                json!({"a": null}),
+                json!({"b": "c"}),
+            ),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!([1, 2]),
+                json!({"a": "b", "c": null}),
+                json!({"a": "b"}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ] {
+            let mut merged = target.clone();
+            merged.merge_patch(&patch);
+            assert_eq!(merged, result, "{} with {}", target, patch);
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let from = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged",
+            "count": 1.0
+        });
+        let to = json!({
+            "title": "Hello!",
+            "author": {"givenName": "John"},
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890",
+            "count": 1
+        });
+        let patch = diff(&from, &to);
+        assert_eq!(
+            patch,
+            json!({
+                "title": "Hello!",
+                "author": {"familyName": null},
+                "tags": ["example"],
+                "phoneNumber": "+01-123-456-7890"
+            })
+        );
+        let mut merged = from.clone();
+        merged.merge_patch(&patch);
+        assert!(merged.json_eq(&to));
+
+        assert_eq!(diff(&to, &to), json!({}));
+        assert_eq!(diff(&json!([1]), &json!([1])), json!([1]));
+        assert_eq!(diff(&json!({"a": 1}), &json!(null)), json!(null));
+        assert_eq!(diff(&json!(1), &json!({"a": {}})), json!({"a": {}}));
+    }
+}
@@ -0,0 +1,347 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A JSON number. Integers that fit in an `i64` or `u64` are stored exactly,
+/// everything else as an `f64`.
+///
+/// With the `arbitrary_precision` feature the number keeps the exact text of its
+/// literal instead, so any number round-trips byte-for-byte through parse and serialize.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Number {
+    n: N,
+}
+
+#[cfg(not(feature = "arbitrary_precision"))]
+#[derive(Debug, Clone, PartialEq)]
+enum N {
+    PosInt(u64),
+    /// Always below zero
+    NegInt(i64),
+    Float(f64),
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[derive(Debug, Clone, PartialEq)]
+enum N {
+    /// Text following the JSON number grammar
+    Literal(String),
+    /// NaN and the infinities, which have no literal
+    NonFinite(f64),
+}
+
+#[cfg(not(feature = "arbitrary_precision"))]
+impl Number {
+    /// Picks the representation for a literal, which has to follow the JSON number
+    /// grammar: integers without fraction or exponent that fit in 64 bits stay integers.
+    pub(crate) fn from_literal(literal: &str) -> Number {
+        if !literal.contains(['.', 'e', 'E']) {
+            if let Ok(num) = literal.parse::<u64>() {
+                return num.into();
+            }
+            match literal.parse::<i64>() {
+                // "-0" has to keep its sign, which only a float can do
+                Ok(0) => return (-0.0).into(),
+                Ok(num) => return num.into(),
+                Err(_) => {}
+            }
+        }
+        // Every literal in the grammar is a valid float, too large ones become infinity and
+        // are rejected by the tokenizer
+        literal.parse::<f64>().unwrap().into()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !matches!(self.n, N::Float(_))
+    }
+
+    /// The number as an `i64`, if it is an integer in its range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(num) => i64::try_from(num).ok(),
+            N::NegInt(num) => Some(num),
+            N::Float(_) => None,
+        }
+    }
+
+    /// The number as a `u64`, if it is a non-negative integer.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(num) => Some(num),
+            N::NegInt(_) | N::Float(_) => None,
+        }
+    }
+
+    /// The number as an `f64`, rounded if it is an integer above 2^53.
+    pub fn as_f64(&self) -> f64 {
+        match self.n {
+            N::PosInt(num) => num as f64,
+            N::NegInt(num) => num as f64,
+            N::Float(num) => num,
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary_precision")]
+impl Number {
+    /// Keeps the literal as it is, it has to follow the JSON number grammar.
+    pub(crate) fn from_literal(literal: &str) -> Number {
+        Number {
+            n: N::Literal(literal.to_string()),
+        }
+    }
+
+    /// The exact text of the number, `None` for NaN and the infinities.
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.n {
+            N::Literal(literal) => Some(literal),
+            N::NonFinite(_) => None,
+        }
+    }
+
+    /// True for literals without a fraction or exponent, however many digits they have.
+    pub fn is_integer(&self) -> bool {
+        match &self.n {
+            N::Literal(literal) => !literal.contains(['.', 'e', 'E']),
+            N::NonFinite(_) => false,
+        }
+    }
+
+    /// The number as an `i64`, if it is an integer in its range.
+    pub fn as_i64(&self) -> Option<i64> {
+        match &self.n {
+            N::Literal(literal) if self.is_integer() => literal.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The number as a `u64`, if it is a non-negative integer in its range.
+    pub fn as_u64(&self) -> Option<u64> {
+        match &self.n {
+            N::Literal(literal) if self.is_integer() => literal.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The closest `f64` to the number.
+    pub fn as_f64(&self) -> f64 {
+        match &self.n {
+            // Every literal in the grammar is a valid float, too large ones become infinity
+            N::Literal(literal) => literal.parse().unwrap(),
+            N::NonFinite(num) => *num,
+        }
+    }
+}
+
+impl Number {
+    pub fn is_f64(&self) -> bool {
+        !self.is_integer()
+    }
+
+    /// Orders numbers by their value, so `1` equals `1.0`. Integers are compared exactly,
+    /// anything else as floats, which leaves NaN unordered.
+    pub(crate) fn compare(&self, other: &Number) -> Option<Ordering> {
+        let integer = |num: &Number| {
+            num.as_i64()
+                .map(i128::from)
+                .or_else(|| num.as_u64().map(i128::from))
+        };
+        match (integer(self), integer(other)) {
+            (Some(left), Some(right)) => Some(left.cmp(&right)),
+            _ => self.as_f64().partial_cmp(&other.as_f64()),
+        }
+    }
+}
+
+/// The shortest text that parses back to the same finite `f64` or `f32`, always with a
+/// fraction or exponent so it stays a float.
+pub(crate) fn format_float<F>(num: F) -> String
+where
+    F: Copy + Into<f64> + fmt::Display + fmt::LowerExp,
+{
+    // Exponents keep very large and very small numbers from turning into hundreds of zeros
+    let abs = num.into().abs();
+    let mut text = if abs >= 1e21 || (abs != 0.0 && abs < 1e-6) {
+        format!("{:e}", num)
+    } else {
+        format!("{}", num)
+    };
+    if !text.contains(['.', 'e']) {
+        text.push_str(".0");
+    }
+    text
+}
+
+#[cfg(not(feature = "arbitrary_precision"))]
+macro_rules! from_unsigned {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(num: $ty) -> Self {
+                    Number { n: N::PosInt(num as u64) }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(not(feature = "arbitrary_precision"))]
+macro_rules! from_signed {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(num: $ty) -> Self {
+                    let n = if num < 0 {
+                        N::NegInt(num as i64)
+                    } else {
+                        N::PosInt(num as u64)
+                    };
+                    Number { n }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "arbitrary_precision")]
+macro_rules! from_integer {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Number {
+                fn from(num: $ty) -> Self {
+                    Number { n: N::Literal(num.to_string()) }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(not(feature = "arbitrary_precision"))]
+from_unsigned!(u8 u16 u32 u64 usize);
+#[cfg(not(feature = "arbitrary_precision"))]
+from_signed!(i8 i16 i32 i64 isize);
+#[cfg(feature = "arbitrary_precision")]
+from_integer!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl From<f64> for Number {
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn from(num: f64) -> Self {
+        Number { n: N::Float(num) }
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn from(num: f64) -> Self {
+        let n = if num.is_finite() {
+            N::Literal(format_float(num))
+        } else {
+            N::NonFinite(num)
+        };
+        Number { n }
+    }
+}
+
+/// Keeps the digits the `f32` is written with, so `0.1f32` stays 0.1 instead of the
+/// `f64` it widens to.
+impl From<f32> for Number {
+    fn from(num: f32) -> Self {
+        if !num.is_finite() {
+            return f64::from(num).into();
+        }
+        // The shortest text of an f32 is always a valid f64
+        format_float(num).parse::<f64>().unwrap().into()
+    }
+}
+
+/// Writes the number as JSON text. Integers are written as they are, floats with the
+/// shortest digits that parse back to the same `f64` and always with a fraction or
+/// exponent, so they stay floats. JSON has no NaN or infinity, so those are written as `null`.
+impl fmt::Display for Number {
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.n {
+            N::PosInt(num) => write!(f, "{}", num),
+            N::NegInt(num) => write!(f, "{}", num),
+            N::Float(num) if !num.is_finite() => f.write_str("null"),
+            N::Float(num) => f.write_str(&format_float(num)),
+        }
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.n {
+            N::Literal(literal) => f.write_str(literal),
+            N::NonFinite(_) => f.write_str("null"),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn test_literal_representation() {
+        let max = Number::from_literal("18446744073709551615");
+        assert_eq!(max.as_u64(), Some(u64::MAX));
+        assert_eq!(max.as_i64(), None);
+
+        let min = Number::from_literal("-9223372036854775808");
+        assert_eq!(min.as_i64(), Some(i64::MIN));
+        assert_eq!(min.as_u64(), None);
+
+        let snowflake = Number::from_literal("1234567890123456789");
+        assert!(snowflake.is_integer());
+        assert_eq!(snowflake.as_i64(), Some(1234567890123456789));
+
+        let too_big = Number::from_literal("18446744073709551616");
+        assert!(too_big.is_f64());
+        assert_eq!(too_big.as_f64(), 18446744073709551616.0);
+
+        for literal in ["1.0", "1e2", "-0", "0.5"] {
+            assert!(Number::from_literal(literal).is_f64(), "{}", literal);
+        }
+        assert_eq!(Number::from_literal("-3").as_i64(), Some(-3));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Number::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(Number::from(-42).to_string(), "-42");
+        assert_eq!(Number::from(2.0).to_string(), "2.0");
+        assert_eq!(Number::from(-0.0).to_string(), "-0.0");
+        assert_eq!(Number::from(0.25).to_string(), "0.25");
+        assert_eq!(Number::from(1e21).to_string(), "1e21");
+        assert_eq!(Number::from(f64::NAN).to_string(), "null");
+        assert_eq!(Number::from(0.1f32).to_string(), "0.1");
+        assert_eq!(Number::from(f32::INFINITY).to_string(), "null");
+    }
+
+    #[test]
+    fn test_equality() {
+        assert_eq!(Number::from(5u8), Number::from(5i64));
+        assert_ne!(Number::from(5), Number::from(5.0));
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary_precision")]
+    fn test_exact_literals() {
+        for literal in [
+            "0.1000000000000000055511151231257827",
+            "123456789012345678901234567890",
+            "-1.50E+300",
+            "1e400",
+        ] {
+            let number = Number::from_literal(literal);
+            assert_eq!(number.to_string(), literal);
+            assert_eq!(number.as_str(), Some(literal));
+        }
+
+        let big = Number::from_literal("123456789012345678901234567890");
+        assert!(big.is_integer());
+        assert_eq!(big.as_u64(), None);
+        assert_eq!(big.as_f64(), 1.2345678901234568e29);
+        assert_eq!(Number::from_literal("-7").as_i64(), Some(-7));
+        assert!(Number::from_literal("1e2").is_f64());
+        assert_eq!(Number::from(f64::INFINITY).as_str(), None);
+    }
+}
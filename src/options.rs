@@ -0,0 +1,31 @@
+/// Settings that change how lenient the parser is with its input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseOptions {
+    /// Replace unpaired UTF-16 surrogates in `\uXXXX` escapes with U+FFFD
+    /// instead of rejecting the string.
+    pub replace_lone_surrogates: bool,
+    /// How many arrays and objects can be nested inside each other, 128 by default.
+    pub max_depth: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            replace_lone_surrogates: false,
+            max_depth: 128,
+        }
+    }
+}
+
+/// Settings for `diff_with_options`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DiffOptions {
+    /// Line arrays up by their longest common subsequence, so inserting or removing an
+    /// element gives one `add` or `remove` instead of changes to every element after it.
+    /// Takes time and memory proportional to the product of the array lengths.
+    pub lcs_arrays: bool,
+    /// Turn a `remove` and an `add` of equal values into a `move`. The moves found in a
+    /// diff are made on a copy of the document, which is diffed again, so this costs a few
+    /// more diffs and time proportional to the square of the number of moves.
+    pub detect_moves: bool,
+}
@@ -1,32 +1,65 @@
 mod parser_state;
 
-use crate::tokenizer::{token::Token, Tokenizer};
+use crate::error::{Error, Position};
+use crate::map::Map;
+use crate::options::ParseOptions;
+use crate::tokenizer::{
+    token::{Span, Token},
+    Tokenizer,
+};
 use crate::value::Value;
 use parser_state::ParserState;
-use std::collections::HashMap;
-use std::error::Error;
 
 pub struct Parser {
-    token_stream: Vec<Token>,
+    token_stream: Vec<(Token, Span)>,
     index: usize,
-    state: ParserState,
+    /// The tokenizer error that cut the token stream short, if any
+    token_error: Option<Error>,
+    /// Where the token stream ends
+    end: Position,
+    depth: usize,
+    options: ParseOptions,
 }
 
 impl Parser {
     pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
         Parser {
             token_stream: vec![],
             index: 0,
-            state: ParserState::Idle,
+            token_error: None,
+            end: Position::default(),
+            depth: 0,
+            options,
         }
     }
 
     pub fn read_into_stream(&mut self, input: &str) {
-        let mut tokenizer = Tokenizer::new(input);
-        self.token_stream = tokenizer.tokenize();
+        let mut tokenizer = Tokenizer::with_options(input, self.options);
+        self.token_stream = vec![];
+        self.index = 0;
+        self.token_error = None;
+        loop {
+            match tokenizer.next_token() {
+                Ok(Some(token)) => self.token_stream.push(token),
+                Ok(None) => {
+                    self.end = tokenizer.position();
+                    break;
+                }
+                Err(e) => {
+                    // Tokenizer errors always have a position
+                    self.end = e.position().unwrap_or_default();
+                    self.token_error = Some(e);
+                    break;
+                }
+            }
+        }
     }
 
-    fn get_token(&mut self) -> Option<Token> {
+    fn get_token(&mut self) -> Option<(Token, Span)> {
         if self.index >= self.token_stream.len() {
             return None;
         }
@@ -35,226 +68,191 @@ impl Parser {
 
         Some(tok)
     }
-    fn parse_arr(&mut self) -> Result<Value, Box<dyn Error>> {
-        let mut arr: Vec<Value> = Vec::new();
 
-        loop {
-            let tok: Token;
-            match self.get_token() {
-                Some(token) => tok = token,
-                None => {
-                    break;
-                }
+    /// The error for running out of tokens: either the input really ended,
+    /// or the tokenizer could not read any further.
+    fn end_error(&self, expected: &'static str) -> Error {
+        match &self.token_error {
+            Some(e) => e.clone(),
+            None => Error::UnexpectedEof {
+                expected,
+                position: self.end,
+            },
+        }
+    }
+
+    /// Parses a single JSON value that must span the whole token stream.
+    pub fn parse(&mut self) -> Result<Value, Error> {
+        let (tok, span) = self.get_token().ok_or_else(|| self.end_error("a value"))?;
+        let value = self.parse_value(tok, span)?;
+        if let Some((_, span)) = self.get_token() {
+            return Err(Error::TrailingCharacters {
+                position: span.start,
+            });
+        }
+        if self.token_error.is_some() {
+            return Err(Error::TrailingCharacters { position: self.end });
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self, tok: Token, span: Span) -> Result<Value, Error> {
+        match tok {
+            Token::String { value } => Ok(Value::String(value)),
+            Token::Number { value } => Ok(Value::Number(value)),
+            Token::True => Ok(Value::Bool(true)),
+            Token::False => Ok(Value::Bool(false)),
+            Token::Null => Ok(Value::Null),
+            Token::OpenCurlyBrace => {
+                self.enter(span.start)?;
+                let obj = self.parse_obj();
+                self.depth -= 1;
+                obj
+            }
+            Token::OpenSquareBrace => {
+                self.enter(span.start)?;
+                let arr = self.parse_arr();
+                self.depth -= 1;
+                arr
             }
-            match self.state {
+            any => Err(Error::UnexpectedToken {
+                expected: "a value",
+                found: any.to_string(),
+                position: span.start,
+            }),
+        }
+    }
+
+    /// Goes one level deeper for the array or object starting at `position`.
+    fn enter(&mut self, position: Position) -> Result<(), Error> {
+        if self.depth >= self.options.max_depth {
+            return Err(Error::DepthLimitExceeded {
+                limit: self.options.max_depth,
+                position,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Parses the rest of an array after its opening '['.
+    fn parse_arr(&mut self) -> Result<Value, Error> {
+        let mut arr: Vec<Value> = Vec::new();
+        let mut state = ParserState::Idle;
+
+        while let Some((tok, span)) = self.get_token() {
+            match state {
                 ParserState::Idle => match tok {
-                    Token::String { value } => {
-                        let val = Value::String(value);
-                        arr.push(val);
-                        self.state = ParserState::GotValue;
-                    }
-                    Token::Number { value } => {
-                        let val = Value::Number(value);
-                        arr.push(val);
-                        self.state = ParserState::GotValue;
-                    }
-                    Token::OpenCurlyBrace => {
-                        let val = self.parse_obj()?;
-                        arr.push(val);
-                        self.state = ParserState::GotValue;
-                    }
-                    Token::ClosedCurlyBrace => Err("Unexpected closing curly brace in the array")?,
-                    Token::OpenSquareBrace => {
-                        let val = self.parse_arr()?;
-                        arr.push(val);
-                        self.state = ParserState::GotValue;
-                    }
-                    Token::ClosedSquareBrace => {
-                        break;
-                    }
-                    Token::Colon => Err("Colon is invalid inside an array")?,
-                    Token::Comma => Err("Unexpected comma")?,
-                    Token::True => {
-                        let val = Value::Bool(true);
-                        arr.push(val);
-                        self.state = ParserState::GotValue;
-                    }
-                    Token::False => {
-                        let val = Value::Bool(false);
-                        arr.push(val);
-                        self.state = ParserState::GotValue;
-                    }
-                    Token::Null => {
-                        let val = Value::Null;
-                        arr.push(val);
-                        self.state = ParserState::GotValue;
+                    Token::ClosedSquareBrace if arr.is_empty() => {
+                        return Ok(Value::Array(arr));
                     }
-                    Token::BadToken {
-                        line_number,
-                        char_number,
-                    } => {
-                        return Err(format!(
-                            "Bad token at line: {}, character: {}",
-                            line_number, char_number
-                        ))?
+                    any => {
+                        arr.push(self.parse_value(any, span)?);
+                        state = ParserState::GotValue;
                     }
                 },
                 ParserState::GotValue => match tok {
-                    Token::Comma => self.state = ParserState::Idle,
+                    Token::Comma => state = ParserState::Idle,
                     Token::ClosedSquareBrace => {
                         return Ok(Value::Array(arr));
                     }
-                    _ => Err("Expected a comma or array ending after a value in the array!")?,
+                    any => {
+                        return Err(Error::UnexpectedToken {
+                            expected: "',' or ']'",
+                            found: any.to_string(),
+                            position: span.start,
+                        })
+                    }
                 },
                 _ => { /*Invalid states*/ }
             }
         }
 
-        Ok(Value::Array(arr))
+        Err(match state {
+            ParserState::GotValue => self.end_error("',' or ']'"),
+            _ => self.end_error("a value"),
+        })
     }
 
-    pub fn parse_obj(&mut self) -> Result<Value, Box<dyn Error>> {
-        let mut res = Value::Object(HashMap::new());
+    /// Parses the rest of an object after its opening '{'.
+    fn parse_obj(&mut self) -> Result<Value, Error> {
+        let mut obj = Map::new();
         let mut val_name = String::new();
+        let mut state = ParserState::Idle;
 
-        loop {
-            let tok: Token;
-            match self.get_token() {
-                Some(token) => tok = token,
-                None => {
-                    break;
-                }
-            }
-            match self.state {
-                ParserState::Idle => {
-                    match tok {
-                        Token::String { value } => {
-                            val_name = value.clone();
-                            self.state = ParserState::GotName;
-                        }
-                        Token::Number { value } => {
-                            return Ok(Value::Number(value));
-                        }
-                        Token::OpenCurlyBrace => { /*Ignore*/ }
-                        Token::ClosedCurlyBrace => {
-                            return Ok(res);
-                        }
-                        Token::OpenSquareBrace => {
-                            return Ok(self.parse_arr()?);
-                        }
-                        Token::ClosedSquareBrace => {}
-                        Token::Colon => Err("Expected a key or a value, got ':'")?,
-                        Token::Comma => Err("Expected a key or a value, got ','")?,
-                        Token::True => {
-                            return Ok(Value::Bool(true));
-                        }
-                        Token::False => {
-                            return Ok(Value::Bool(false));
-                        }
-                        Token::Null => {
-                            return Ok(Value::Null);
-                        }
-                        Token::BadToken {
-                            line_number,
-                            char_number,
-                        } => {
-                            return Err(format!(
-                                "Bad token at line: {}, character: {}",
-                                line_number, char_number
-                            ))?
-                        }
+        while let Some((tok, span)) = self.get_token() {
+            match state {
+                ParserState::Idle => match tok {
+                    Token::String { value } => {
+                        val_name = value;
+                        state = ParserState::GotName;
                     }
-                }
+                    Token::ClosedCurlyBrace if obj.is_empty() => {
+                        return Ok(Value::Object(obj));
+                    }
+                    any => {
+                        return Err(Error::UnexpectedToken {
+                            expected: "a key",
+                            found: any.to_string(),
+                            position: span.start,
+                        })
+                    }
+                },
                 ParserState::GotName => match tok {
                     Token::Colon => {
-                        self.state = ParserState::GotColon;
+                        state = ParserState::GotColon;
+                    }
+                    any => {
+                        return Err(Error::UnexpectedToken {
+                            expected: "a colon (':')",
+                            found: any.to_string(),
+                            position: span.start,
+                        })
                     }
-                    any => return Err(format!("Expected a colon (':'), got '{:?}'", any))?,
                 },
                 ParserState::GotColon => {
-                    match tok {
-                        Token::String { value } => {
-                            let val = Value::String(value);
-                            if let Value::Object(hm) = &mut res {
-                                hm.insert(val_name.clone(), val);
-                            }
-                        }
-                        Token::Number { value } => {
-                            let val = Value::Number(value);
-                            if let Value::Object(hm) = &mut res {
-                                hm.insert(val_name.clone(), val);
-                            }
-                        }
-                        Token::OpenCurlyBrace => {
-                            self.state = ParserState::Idle;
-                            let val = self.parse_obj()?;
-                            if let Value::Object(hm) = &mut res {
-                                hm.insert(val_name.clone(), val);
-                            }
-                        }
-                        Token::ClosedCurlyBrace => Err("Expected a value, got '}'")?,
-                        Token::OpenSquareBrace => {
-                            self.state = ParserState::Idle;
-                            let val = self.parse_arr()?;
-                            if let Value::Object(hm) = &mut res {
-                                hm.insert(val_name.clone(), val);
-                            }
-                        }
-                        Token::ClosedSquareBrace => {}
-                        Token::Colon => Err("Expected a value, got ':'")?,
-                        Token::Comma => Err("Expected a value, got ','")?,
-                        Token::True => {
-                            if let Value::Object(hm) = &mut res {
-                                hm.insert(val_name.clone(), Value::Bool(true));
-                            }
-                        }
-                        Token::False => {
-                            if let Value::Object(hm) = &mut res {
-                                hm.insert(val_name.clone(), Value::Bool(false));
-                            }
-                        }
-                        Token::Null => {
-                            if let Value::Object(hm) = &mut res {
-                                hm.insert(val_name.clone(), Value::Null);
-                            }
-                        }
-                        Token::BadToken {
-                            line_number,
-                            char_number,
-                        } => {
-                            return Err(format!(
-                                "Bad token at line: {}, character: {}",
-                                line_number, char_number
-                            ))?
-                        }
-                    }
-                    self.state = ParserState::GotValue;
+                    let val = self.parse_value(tok, span)?;
+                    obj.insert(std::mem::take(&mut val_name), val);
+                    state = ParserState::GotValue;
                 }
                 ParserState::GotValue => match tok {
                     Token::Comma => {
-                        self.state = ParserState::Idle;
+                        state = ParserState::Idle;
                     }
                     Token::ClosedCurlyBrace => {
-                        return Ok(res);
+                        return Ok(Value::Object(obj));
+                    }
+                    any => {
+                        return Err(Error::UnexpectedToken {
+                            expected: "',' or '}'",
+                            found: any.to_string(),
+                            position: span.start,
+                        })
                     }
-                    any => Err(format!("Excpected a ',' or a '}}', got '{:?}'", any))?,
                 },
             }
         }
-        Ok(res)
+
+        Err(match state {
+            ParserState::Idle => self.end_error("a key"),
+            ParserState::GotName => self.end_error("a colon (':')"),
+            ParserState::GotColon => self.end_error("a value"),
+            ParserState::GotValue => self.end_error("',' or '}'"),
+        })
     }
 }
 
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::number::Number;
 
     #[test]
     #[should_panic]
     fn test_error_handling_1() {
         let mut parser = Parser::new();
         parser.read_into_stream("{\"age\":32f}");
-        parser.parse_obj().unwrap();
+        parser.parse().unwrap();
     }
 
     #[test]
@@ -262,7 +260,7 @@ pub mod test {
     fn test_error_handling_2() {
         let mut parser = Parser::new();
         parser.read_into_stream("{\"age");
-        parser.parse_obj().unwrap();
+        parser.parse().unwrap();
     }
 
     #[test]
@@ -270,7 +268,7 @@ pub mod test {
     fn test_error_handling_3() {
         let mut parser = Parser::new();
         parser.read_into_stream("{\"false\":fald}");
-        parser.parse_obj().unwrap();
+        parser.parse().unwrap();
     }
 
     #[test]
@@ -278,7 +276,7 @@ pub mod test {
     fn test_error_handling_4() {
         let mut parser = Parser::new();
         parser.read_into_stream("{\"true\":truj}");
-        parser.parse_obj().unwrap();
+        parser.parse().unwrap();
     }
 
     #[test]
@@ -286,25 +284,22 @@ pub mod test {
     fn test_error_handling() {
         let mut parser = Parser::new();
         parser.read_into_stream("{\"age\":32.4.5}");
-        parser.parse_obj().unwrap();
+        parser.parse().unwrap();
     }
 
     #[test]
     fn test_value_extraction() {
         let mut parser = Parser::new();
         parser.read_into_stream("{\"name\":\"Wazowski\"}");
-        let obj = parser.parse_obj().expect("should be ok").get_obj().unwrap();
-        assert_eq!(
-            obj["name"].clone().get_str().unwrap(),
-            "Wazowski".to_string()
-        );
+        let obj = parser.parse().expect("should be ok").get_obj().unwrap();
+        assert_eq!(obj["name"].as_str(), Some("Wazowski"));
     }
 
     #[test]
     fn test_simple_json() {
         let mut parser = Parser::new();
         parser.read_into_stream("{\"name\":\"Wazowski\"}");
-        let res = parser.parse_obj().expect("should be ok");
+        let res = parser.parse().expect("should be ok");
         if let Value::Object(map) = res {
             assert_eq!(map["name"], Value::String("Wazowski".to_string()));
         }
@@ -320,10 +315,10 @@ pub mod test {
                                     \"alive\": true\
                                  }",
         );
-        let res = parser.parse_obj().expect("should work");
+        let res = parser.parse().expect("should work");
         if let Value::Object(map) = res {
             assert_eq!(map["name"], Value::String("Mike".to_string()));
-            assert_eq!(map["age"], Value::Number(21.0));
+            assert_eq!(map["age"], Value::Number(Number::from(21)));
             assert_eq!(map["alive"], Value::Bool(true));
         }
     }
@@ -338,7 +333,7 @@ pub mod test {
                                                 }\
                                  }",
         );
-        let res = parser.parse_obj().expect("this will actualy never fail");
+        let res = parser.parse().expect("this will actualy never fail");
         if let Value::Object(map) = res {
             let obj = &map["object"];
             if let Value::Object(object) = obj {
@@ -352,11 +347,11 @@ pub mod test {
     fn test_simple_json_array() {
         let mut parser = Parser::new();
         parser.read_into_stream("[1,2,3]");
-        let res = parser.parse_obj().expect("I cannot make an error");
+        let res = parser.parse().expect("I cannot make an error");
         if let Value::Array(arr) = res {
-            assert_eq!(arr[0], Value::Number(1.0));
-            assert_eq!(arr[1], Value::Number(2.0));
-            assert_eq!(arr[2], Value::Number(3.0));
+            assert_eq!(arr[0], Value::Number(Number::from(1)));
+            assert_eq!(arr[1], Value::Number(Number::from(2)));
+            assert_eq!(arr[2], Value::Number(Number::from(3)));
         } else {
             panic!("Result is not an array!");
         }
@@ -366,11 +361,11 @@ pub mod test {
     fn test_json_array() {
         let mut parser = Parser::new();
         parser.read_into_stream("{ \"stuff\": [1, false, \"foo\"] }");
-        let res = parser.parse_obj().expect("will be fine");
+        let res = parser.parse().expect("will be fine");
         if let Value::Object(obj) = res {
             let array = &obj["stuff"];
             if let Value::Array(arr) = array {
-                assert_eq!(arr[0], Value::Number(1.0));
+                assert_eq!(arr[0], Value::Number(Number::from(1)));
                 assert_eq!(arr[1], Value::Bool(false));
                 assert_eq!(arr[2], Value::String("foo".to_string()));
             } else {
@@ -380,4 +375,166 @@ pub mod test {
             panic!("Result is not an object!");
         }
     }
+
+    #[test]
+    fn test_null_values() {
+        let mut parser = Parser::new();
+        parser.read_into_stream("{ \"nothing\": null, \"list\": [null, 1] }");
+        let res = parser.parse().expect("null is a valid value");
+        if let Value::Object(obj) = res {
+            assert_eq!(obj["nothing"], Value::Null);
+            assert_eq!(
+                obj["list"],
+                Value::Array(vec![Value::Null, Value::Number(Number::from(1))])
+            );
+        } else {
+            panic!("Result is not an object!");
+        }
+    }
+
+    #[test]
+    fn test_top_level_null() {
+        let mut parser = Parser::new();
+        parser.read_into_stream("null");
+        assert!(parser.parse().expect("null is a valid document").is_null());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bad_null() {
+        let mut parser = Parser::new();
+        parser.read_into_stream("{\"nothing\":nul}");
+        parser.parse().unwrap();
+    }
+
+    #[test]
+    fn test_negative_and_exponent_numbers() {
+        let mut parser = Parser::new();
+        parser.read_into_stream("{\"balance\": -12.5,\n\"reading\": 4e-2}");
+        let res = parser.parse().expect("numbers are valid");
+        if let Value::Object(obj) = res {
+            assert_eq!(obj["balance"], Value::Number(Number::from(-12.5)));
+            assert_eq!(obj["reading"].as_f64(), Some(0.04));
+        } else {
+            panic!("Result is not an object!");
+        }
+    }
+
+    #[test]
+    fn test_escaped_string_values() {
+        let mut parser = Parser::new();
+        parser.read_into_stream(r#"{"quote": "say \"hi\"", "path": "C:\\temp"}"#);
+        let res = parser.parse().expect("escapes are valid");
+        if let Value::Object(obj) = res {
+            assert_eq!(obj["quote"], Value::String("say \"hi\"".to_string()));
+            assert_eq!(obj["path"], Value::String("C:\\temp".to_string()));
+        } else {
+            panic!("Result is not an object!");
+        }
+    }
+
+    #[test]
+    fn test_top_level_scalars() {
+        for (input, expected) in [
+            ("\"key\"", Value::String("key".to_string())),
+            ("-4.5", Value::Number(Number::from(-4.5))),
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+            (" null ", Value::Null),
+        ] {
+            let mut parser = Parser::new();
+            parser.read_into_stream(input);
+            assert_eq!(parser.parse().expect("should be ok"), expected);
+        }
+    }
+
+    #[test]
+    fn test_trailing_content() {
+        for input in ["{} garbage", "[1] [2]", "\"a\" \"b\"", "1 2", "{},"] {
+            let mut parser = Parser::new();
+            parser.read_into_stream(input);
+            assert!(parser.parse().is_err(), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_incomplete_documents() {
+        for input in ["", "[1,", "[1", "{\"a\":1", "{\"a\":", "[1,]", "{\"a\":1,}"] {
+            let mut parser = Parser::new();
+            parser.read_into_stream(input);
+            assert!(parser.parse().is_err(), "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let position = |column: usize| Position {
+            line: 1,
+            column,
+            offset: column - 1,
+        };
+        for (input, expected) in [
+            (
+                "[1 2]",
+                Error::UnexpectedToken {
+                    expected: "',' or ']'",
+                    found: "the number 2".to_string(),
+                    position: position(4),
+                },
+            ),
+            (
+                "{\"a\" 1}",
+                Error::UnexpectedToken {
+                    expected: "a colon (':')",
+                    found: "the number 1".to_string(),
+                    position: position(6),
+                },
+            ),
+            (
+                "{\"a\":",
+                Error::UnexpectedEof {
+                    expected: "a value",
+                    position: position(6),
+                },
+            ),
+            (
+                "[1] x",
+                Error::TrailingCharacters {
+                    position: position(5),
+                },
+            ),
+            (
+                "[1, \"\\q\"]",
+                Error::InvalidEscape {
+                    position: position(6),
+                },
+            ),
+        ] {
+            let mut parser = Parser::new();
+            parser.read_into_stream(input);
+            assert_eq!(parser.parse(), Err(expected), "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let mut parser = Parser::with_options(ParseOptions {
+            max_depth: 2,
+            ..ParseOptions::default()
+        });
+        parser.read_into_stream("[[1]]");
+        assert!(parser.parse().is_ok());
+        parser.read_into_stream("[[[1]]]");
+        assert_eq!(
+            parser.parse(),
+            Err(Error::DepthLimitExceeded {
+                limit: 2,
+                position: Position {
+                    line: 1,
+                    column: 3,
+                    offset: 2
+                }
+            })
+        );
+    }
 }
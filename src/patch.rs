@@ -0,0 +1,253 @@
+//! JSON Patch (RFC 6902): lists of operations that change a document.
+
+mod diff;
+
+use crate::error::Error;
+use crate::pointer;
+use crate::value::Value;
+pub(crate) use diff::diff;
+
+const NOT_AN_OBJECT: &str = "an operation has to be an object";
+const UNKNOWN_OP: &str = "\"op\" has to be add, remove, replace, move, copy or test";
+const PATH_MISSING: &str = "\"path\" has to be a JSON Pointer string";
+const FROM_MISSING: &str = "\"from\" has to be a JSON Pointer string";
+const VALUE_MISSING: &str = "\"value\" is missing";
+const MALFORMED: &str = "the JSON Pointer is malformed";
+const MISSING_TARGET: &str = "there is no value at the pointer";
+const ADD_MISSING_PARENT: &str = "the object or array to add to does not exist";
+const ADD_NOT_A_CONTAINER: &str = "the value to add to is neither an object nor an array";
+const ADD_INVALID_INDEX: &str = "the array index is out of range or not a number";
+const MOVE_INTO_ITSELF: &str = "a value can not be moved into itself";
+const TEST_FAILED: &str = "the value is not the expected one";
+
+/// Applies the operations of `patch`, an array like
+/// `[{"op": "replace", "path": "/a/b", "value": 1}]`, to `doc`.
+///
+/// Either all operations succeed or `doc` is left unchanged. A failing operation gives
+/// `Error::PatchFailed` with its index in the patch and the pointer it failed at.
+pub fn apply(doc: &mut Value, patch: &Value) -> Result<(), Error> {
+    let operations = patch
+        .as_array()
+        .ok_or_else(|| patch.type_error("an array of operations"))?;
+    // Working on a copy is what keeps the document intact when an operation fails
+    let mut patched = doc.clone();
+    for (index, operation) in operations.iter().enumerate() {
+        apply_operation(&mut patched, operation).map_err(|(pointer, reason)| {
+            Error::PatchFailed {
+                index,
+                pointer,
+                reason,
+            }
+        })?;
+    }
+    *doc = patched;
+    Ok(())
+}
+
+/// Failures are the pointer the operation failed at and the reason.
+fn apply_operation(doc: &mut Value, operation: &Value) -> Result<(), (String, &'static str)> {
+    let operation = operation
+        .as_object()
+        .ok_or((String::new(), NOT_AN_OBJECT))?;
+    let path = match operation.get("path") {
+        Some(Value::String(path)) => path.as_str(),
+        _ => return Err((String::new(), PATH_MISSING)),
+    };
+    let fail = |reason| (path.to_string(), reason);
+    pointer::parse(path).map_err(|_| fail(MALFORMED))?;
+    let value = || operation.get("value").ok_or_else(|| fail(VALUE_MISSING));
+    let from = || match operation.get("from") {
+        Some(Value::String(from)) => match pointer::parse(from) {
+            Ok(_) => Ok(from.as_str()),
+            Err(_) => Err((from.to_string(), MALFORMED)),
+        },
+        _ => Err(fail(FROM_MISSING)),
+    };
+
+    match operation.get("op").and_then(Value::as_str) {
+        Some("add") => add(doc, path, value()?.clone()).map_err(fail),
+        Some("remove") => match doc.pointer_remove(path) {
+            Some(_) => Ok(()),
+            None => Err(fail(MISSING_TARGET)),
+        },
+        Some("replace") => {
+            let value = value()?.clone();
+            let target = doc.pointer_mut(path).ok_or_else(|| fail(MISSING_TARGET))?;
+            *target = value;
+            Ok(())
+        }
+        Some("move") => {
+            let from = from()?;
+            if path == from {
+                return match doc.pointer(from) {
+                    Some(_) => Ok(()),
+                    None => Err((from.to_string(), MISSING_TARGET)),
+                };
+            }
+            if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err(fail(MOVE_INTO_ITSELF));
+            }
+            let moved = doc
+                .pointer_remove(from)
+                .ok_or_else(|| (from.to_string(), MISSING_TARGET))?;
+            add(doc, path, moved).map_err(fail)
+        }
+        Some("copy") => {
+            let from = from()?;
+            let copied = doc
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| (from.to_string(), MISSING_TARGET))?;
+            add(doc, path, copied).map_err(fail)
+        }
+        Some("test") => {
+            let expected = value()?;
+            match doc.pointer(path) {
+                Some(found) if found.json_eq(expected) => Ok(()),
+                Some(_) => Err(fail(TEST_FAILED)),
+                None => Err(fail(MISSING_TARGET)),
+            }
+        }
+        _ => Err(fail(UNKNOWN_OP)),
+    }
+}
+
+/// Adds `value` at the valid pointer `path`. Unlike `Value::pointer_set`, an array
+/// index inserts before the element there instead of replacing it.
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<(), &'static str> {
+    let mut tokens = pointer::parse(path).map_err(|_| MALFORMED)?;
+    let last = match tokens.pop() {
+        Some(last) => last,
+        None => {
+            *doc = value;
+            return Ok(());
+        }
+    };
+    match doc.walk_mut(&tokens) {
+        Some(Value::Object(obj)) => {
+            obj.insert(last, value);
+            Ok(())
+        }
+        Some(Value::Array(arr)) => {
+            let index = match last.as_str() {
+                "-" => arr.len(),
+                _ => pointer::array_index(&last)
+                    .filter(|index| *index <= arr.len())
+                    .ok_or(ADD_INVALID_INDEX)?,
+            };
+            arr.insert(index, value);
+            Ok(())
+        }
+        Some(_) => Err(ADD_NOT_A_CONTAINER),
+        None => Err(ADD_MISSING_PARENT),
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn test_operations() {
+        let mut doc = json!({"a": {"b": [1, 2]}, "c": "x"});
+        let patch = json!([
+            {"op": "add", "path": "/a/b/1", "value": 9},
+            {"op": "add", "path": "/a/b/-", "value": 3},
+            {"op": "add", "path": "/d", "value": {}},
+            {"op": "remove", "path": "/a/b/0"},
+            {"op": "replace", "path": "/c", "value": "y"},
+            {"op": "copy", "from": "/c", "path": "/d/e"},
+            {"op": "move", "from": "/a/b", "path": "/d/f"},
+            {"op": "test", "path": "/d/f", "value": [9, 2.0, 3]},
+            {"op": "move", "from": "/c", "path": "/c"}
+        ]);
+        apply(&mut doc, &patch).unwrap();
+        assert_eq!(
+            doc.to_string(),
+            "{\"a\":{},\"c\":\"y\",\"d\":{\"e\":\"y\",\"f\":[9,2,3]}}"
+        );
+
+        apply(&mut doc, &json!([{"op": "add", "path": "", "value": [1]}])).unwrap();
+        assert_eq!(doc, json!([1]));
+    }
+
+    #[test]
+    fn test_failures_leave_the_document_unchanged() {
+        let original = json!({"a": [1, 2], "b": {"c": true}});
+        for (patch, index, pointer, reason) in [
+            (
+                json!([{"op": "remove", "path": "/a/0"}, {"op": "remove", "path": "/x"}]),
+                1,
+                "/x",
+                MISSING_TARGET,
+            ),
+            (
+                json!([{"op": "add", "path": "/a/3", "value": 0}]),
+                0,
+                "/a/3",
+                ADD_INVALID_INDEX,
+            ),
+            (
+                json!([{"op": "add", "path": "/x/y", "value": 0}]),
+                0,
+                "/x/y",
+                ADD_MISSING_PARENT,
+            ),
+            (
+                json!([{"op": "add", "path": "/b/c/d", "value": 0}]),
+                0,
+                "/b/c/d",
+                ADD_NOT_A_CONTAINER,
+            ),
+            (
+                json!([{"op": "test", "path": "/b/c", "value": 1}]),
+                0,
+                "/b/c",
+                TEST_FAILED,
+            ),
+            (
+                json!([{"op": "move", "from": "/b", "path": "/b/d"}]),
+                0,
+                "/b/d",
+                MOVE_INTO_ITSELF,
+            ),
+            (
+                json!([{"op": "copy", "from": "b", "path": "/d"}]),
+                0,
+                "b",
+                MALFORMED,
+            ),
+            (json!([{"op": "copy", "path": "/d"}]), 0, "/d", FROM_MISSING),
+            (
+                json!([{"op": "replace", "path": "/a"}]),
+                0,
+                "/a",
+                VALUE_MISSING,
+            ),
+            (json!([{"op": "delete", "path": "/a"}]), 0, "/a", UNKNOWN_OP),
+            (json!([{"op": "remove"}]), 0, "", PATH_MISSING),
+            (json!([[]]), 0, "", NOT_AN_OBJECT),
+        ] {
+            let mut doc = original.clone();
+            let err = apply(&mut doc, &patch).unwrap_err();
+            assert_eq!(
+                err,
+                Error::PatchFailed {
+                    index,
+                    pointer: pointer.to_string(),
+                    reason,
+                },
+                "{}",
+                patch
+            );
+            assert_eq!(doc, original);
+        }
+
+        let err = apply(&mut json!({}), &json!({"op": "remove"})).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The value you are trying to extract is not an array of operations, it is an object!"
+        );
+    }
+}
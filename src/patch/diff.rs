@@ -0,0 +1,560 @@
+use super::{add, MISSING_TARGET};
+use crate::json;
+use crate::options::DiffOptions;
+use crate::pointer;
+use crate::value::Value;
+
+/// An RFC 6902 patch that turns `from` into `to`.
+///
+/// With `detect_moves`, every `remove` that has an equal `add` is turned into a `move`.
+/// The moves are made on a copy of `from`, which is diffed again for values that only line
+/// up after them. The moves come first in the patch, followed by the patch from the moved
+/// copy to `to`.
+pub(crate) fn diff(from: &Value, to: &Value, options: DiffOptions) -> Value {
+    let mut operations = vec![];
+    let mut current = from.clone();
+    let mut changes = Differ::run(from, to, options);
+    // Every move takes the place of a remove, this is just a safeguard
+    let mut moves_left = match options.detect_moves {
+        true => changes
+            .iter()
+            .filter(|change| matches!(change, Change::Remove { .. }))
+            .count(),
+        false => 0,
+    };
+    // All moves of one diff are made at once, diffing again only finds the values that
+    // line up after them
+    while moves_left > 0 {
+        let mut moved = current.clone();
+        let moves = match make_moves(&mut moved, move_candidates(&changes), moves_left) {
+            Ok(moves) if !moves.is_empty() => moves,
+            _ => break,
+        };
+        current = moved;
+        moves_left -= moves.len();
+        operations.extend(moves);
+        changes = Differ::run(&current, to, options);
+    }
+    operations.extend(changes.into_iter().map(Change::into_operation));
+    Value::Array(operations)
+}
+
+/// Pairs each `add` with a `remove` of an equal value, as the pointer of the value and
+/// the pointer to move it to, both in the document being diffed.
+fn move_candidates(changes: &[Change]) -> Vec<(String, String)> {
+    let mut removed: Vec<_> = changes
+        .iter()
+        .filter_map(|change| match change {
+            Change::Remove {
+                original, value, ..
+            } => Some((original, *value)),
+            _ => None,
+        })
+        .collect();
+    let mut candidates = vec![];
+    for change in changes {
+        if let Change::Add {
+            destination, value, ..
+        } = change
+        {
+            if let Some(position) = removed
+                .iter()
+                .position(|(_, removed)| removed.json_eq(value))
+            {
+                let (source, _) = removed.remove(position);
+                candidates.push((source.clone(), destination.clone()));
+            }
+        }
+    }
+    candidates
+}
+
+/// Makes up to `limit` of the `candidates` on `current`, giving their `move` operations.
+/// The pointers of each candidate are adjusted for the moves before it, candidates that
+/// one of them gets in the way of are left for the next diff.
+fn make_moves(
+    current: &mut Value,
+    candidates: Vec<(String, String)>,
+    limit: usize,
+) -> Result<Vec<Value>, &'static str> {
+    let mut edits: Vec<Edit> = vec![];
+    let mut moves = vec![];
+    for (source, destination) in candidates {
+        if moves.len() == limit {
+            break;
+        }
+        let source = edits
+            .iter()
+            .try_fold(tokens(&source), |pointer, edit| edit.adjust(pointer, false));
+        let destination = edits
+            .iter()
+            .try_fold(tokens(&destination), |pointer, edit| {
+                edit.adjust(pointer, true)
+            });
+        let (source, destination) = match (source, destination) {
+            (Some(source), Some(destination)) => (source, destination),
+            _ => continue,
+        };
+        let removal = match (
+            Edit::new(current, source, true),
+            insertable(current, &destination),
+        ) {
+            (Some(removal), true) => removal,
+            _ => continue,
+        };
+        let destination = match removal.adjust(destination, true) {
+            Some(destination) => destination,
+            None => continue,
+        };
+        let (from, path) = (join(&removal.at), join(&destination));
+        let value = current.pointer_remove(&from).ok_or(MISSING_TARGET)?;
+        add(current, &path, value)?;
+        moves.push(json!({"op": "move", "from": from, "path": path}));
+        edits.push(removal);
+        edits.push(Edit::new(current, destination, false).ok_or(MISSING_TARGET)?);
+    }
+    Ok(moves)
+}
+
+/// Whether `add` can insert at `pointer`: its parent is an object, or an array that the
+/// index is at most the length of.
+fn insertable(current: &Value, pointer: &[String]) -> bool {
+    let (last, parent) = match pointer.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    match current.pointer(&join(parent)) {
+        Some(Value::Object(_)) => true,
+        Some(Value::Array(arr)) => {
+            pointer::array_index(last).is_some_and(|index| index <= arr.len())
+        }
+        _ => false,
+    }
+}
+
+/// The escaped reference tokens of a pointer, e.g. `a~1b` and `0` for `/a~1b/0`.
+fn tokens(pointer: &str) -> Vec<String> {
+    pointer.split('/').skip(1).map(str::to_string).collect()
+}
+
+fn join(tokens: &[String]) -> String {
+    tokens.iter().map(|token| format!("/{}", token)).collect()
+}
+
+/// A value removed from or added to the document by a move.
+struct Edit {
+    at: Vec<String>,
+    /// Whether `at` is in an array, whose elements after it shift
+    in_array: bool,
+    removed: bool,
+}
+
+impl Edit {
+    /// The edit at `at`, `None` if there is no value to remove there.
+    fn new(current: &Value, at: Vec<String>, removed: bool) -> Option<Edit> {
+        let (_, parent) = at.split_last()?;
+        let in_array = matches!(current.pointer(&join(parent)), Some(Value::Array(_)));
+        if removed && current.pointer(&join(&at)).is_none() {
+            return None;
+        }
+        Some(Edit {
+            at,
+            in_array,
+            removed,
+        })
+    }
+
+    /// `pointer` once the edit is made, `None` if the edit changes the value it points to.
+    /// A `destination` is where a value is inserted, so only its parent has to stay.
+    fn adjust(&self, mut pointer: Vec<String>, destination: bool) -> Option<Vec<String>> {
+        let (last, parent) = self.at.split_last()?;
+        if parent.starts_with(&pointer) {
+            // The pointer is the array or object edited, or contains it
+            return destination.then_some(pointer);
+        }
+        if !pointer.starts_with(parent) {
+            return Some(pointer);
+        }
+        let depth = parent.len();
+        if !self.in_array {
+            return (pointer[depth] != *last).then_some(pointer);
+        }
+        let (index, at) = match (
+            pointer::array_index(&pointer[depth]),
+            pointer::array_index(last),
+        ) {
+            (Some(index), Some(at)) => (index, at),
+            _ => return Some(pointer),
+        };
+        if self.removed && index == at {
+            // Inserting at the removed index is fine, pointing into the removed value is not
+            return (destination && pointer.len() == self.at.len()).then_some(pointer);
+        }
+        if self.removed && index > at {
+            pointer[depth] = (index - 1).to_string();
+        } else if !self.removed && index >= at {
+            pointer[depth] = (index + 1).to_string();
+        }
+        Some(pointer)
+    }
+}
+
+enum Change<'a> {
+    Add {
+        path: String,
+        value: &'a Value,
+        /// Where the value would go in the document being diffed, for turning it into a move
+        destination: String,
+    },
+    Remove {
+        path: String,
+        /// Where the value is in the document being diffed, `path` can differ after
+        /// earlier changes to the same array
+        original: String,
+        value: &'a Value,
+    },
+    Replace {
+        path: String,
+        value: &'a Value,
+    },
+}
+
+impl Change<'_> {
+    fn into_operation(self) -> Value {
+        match self {
+            Change::Add { path, value, .. } => {
+                json!({"op": "add", "path": path, "value": value.clone()})
+            }
+            Change::Remove { path, .. } => json!({"op": "remove", "path": path}),
+            Change::Replace { path, value } => {
+                json!({"op": "replace", "path": path, "value": value.clone()})
+            }
+        }
+    }
+}
+
+struct Differ<'a> {
+    options: DiffOptions,
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Differ<'a> {
+    fn run(from: &'a Value, to: &'a Value, options: DiffOptions) -> Vec<Change<'a>> {
+        let mut differ = Differ {
+            options,
+            changes: vec![],
+        };
+        differ.diff(from, to, "", "");
+        differ.changes
+    }
+
+    /// `path` is where `from` is once the changes so far are applied, `original` where
+    /// it is in the document being diffed.
+    fn diff(&mut self, from: &'a Value, to: &'a Value, path: &str, original: &str) {
+        match (from, to) {
+            (Value::Object(from), Value::Object(to)) => {
+                for (key, value) in from.iter() {
+                    let token = pointer::escape(key);
+                    let path = format!("{}/{}", path, token);
+                    let original = format!("{}/{}", original, token);
+                    match to.get(key) {
+                        Some(target) => self.diff(value, target, &path, &original),
+                        None => self.changes.push(Change::Remove {
+                            path,
+                            original,
+                            value,
+                        }),
+                    }
+                }
+                for (key, value) in to.iter().filter(|(key, _)| !from.contains_key(key)) {
+                    let token = pointer::escape(key);
+                    self.changes.push(Change::Add {
+                        path: format!("{}/{}", path, token),
+                        value,
+                        destination: format!("{}/{}", original, token),
+                    });
+                }
+            }
+            (Value::Array(from), Value::Array(to)) if self.options.lcs_arrays => {
+                self.diff_lcs(from, to, path, original)
+            }
+            (Value::Array(from), Value::Array(to)) => self.diff_by_index(from, to, path, original),
+            _ if from.json_eq(to) => {}
+            _ => self.changes.push(Change::Replace {
+                path: path.to_string(),
+                value: to,
+            }),
+        }
+    }
+
+    fn diff_by_index(&mut self, from: &'a [Value], to: &'a [Value], path: &str, original: &str) {
+        for (index, (from, to)) in from.iter().zip(to).enumerate() {
+            let path = format!("{}/{}", path, index);
+            let original = format!("{}/{}", original, index);
+            self.diff(from, to, &path, &original);
+        }
+        // From the end, so the indices of the elements still to remove stay the same
+        for index in (to.len()..from.len()).rev() {
+            self.changes.push(Change::Remove {
+                path: format!("{}/{}", path, index),
+                original: format!("{}/{}", original, index),
+                value: &from[index],
+            });
+        }
+        for (index, value) in to.iter().enumerate().skip(from.len()) {
+            self.changes.push(Change::Add {
+                path: format!("{}/{}", path, index),
+                value,
+                destination: format!("{}/{}", original, from.len()),
+            });
+        }
+    }
+
+    fn diff_lcs(&mut self, from: &'a [Value], to: &'a [Value], path: &str, original: &str) {
+        // Equal elements at the start and the end need no table
+        let shorter = from.len().min(to.len());
+        let prefix = (0..shorter)
+            .take_while(|&i| from[i].json_eq(&to[i]))
+            .count();
+        let suffix = (0..shorter - prefix)
+            .take_while(|&i| from[from.len() - 1 - i].json_eq(&to[to.len() - 1 - i]))
+            .count();
+        let old = &from[prefix..from.len() - suffix];
+        let new = &to[prefix..to.len() - suffix];
+
+        // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+        let mut lengths = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lengths[i][j] = if old[i].json_eq(&new[j]) {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let mut gap = Gap {
+            path,
+            original,
+            offset: prefix,
+            index: prefix,
+            removed: vec![],
+            added: vec![],
+        };
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i].json_eq(&new[j]) {
+                gap.close(self, old, new, i);
+                gap.index += 1;
+                i += 1;
+                j += 1;
+            } else if j == new.len() || (i < old.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+                gap.removed.push(i);
+                i += 1;
+            } else {
+                gap.added.push(j);
+                j += 1;
+            }
+        }
+        gap.close(self, old, new, old.len());
+    }
+}
+
+/// Elements of an array between two that line up, removed from the old array or added
+/// in the new one.
+struct Gap<'p> {
+    path: &'p str,
+    original: &'p str,
+    /// Index of `old[0]` in the whole array
+    offset: usize,
+    /// Where the gap starts once the changes so far are applied
+    index: usize,
+    removed: Vec<usize>,
+    added: Vec<usize>,
+}
+
+impl Gap<'_> {
+    /// Turns the gap into changes. Removed and added elements are paired up and diffed with
+    /// each other first, `next` is the old element after the gap.
+    fn close<'a>(
+        &mut self,
+        differ: &mut Differ<'a>,
+        old: &'a [Value],
+        new: &'a [Value],
+        next: usize,
+    ) {
+        let paired = self.removed.len().min(self.added.len());
+        for (&i, &j) in self.removed.iter().zip(&self.added) {
+            let path = format!("{}/{}", self.path, self.index);
+            let original = format!("{}/{}", self.original, self.offset + i);
+            differ.diff(&old[i], &new[j], &path, &original);
+            self.index += 1;
+        }
+        for &i in &self.removed[paired..] {
+            differ.changes.push(Change::Remove {
+                path: format!("{}/{}", self.path, self.index),
+                original: format!("{}/{}", self.original, self.offset + i),
+                value: &old[i],
+            });
+        }
+        for &j in &self.added[paired..] {
+            differ.changes.push(Change::Add {
+                path: format!("{}/{}", self.path, self.index),
+                value: &new[j],
+                destination: format!("{}/{}", self.original, self.offset + next),
+            });
+            self.index += 1;
+        }
+        self.removed.clear();
+        self.added.clear();
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::patch::apply;
+
+    fn check(from: Value, to: Value, options: DiffOptions) -> Value {
+        let patch = diff(&from, &to, options);
+        let mut patched = from;
+        apply(&mut patched, &patch).unwrap();
+        assert!(patched.json_eq(&to), "{} gave {}", patch, patched);
+        patch
+    }
+
+    const LCS: DiffOptions = DiffOptions {
+        lcs_arrays: true,
+        detect_moves: false,
+    };
+    const MOVES: DiffOptions = DiffOptions {
+        lcs_arrays: true,
+        detect_moves: true,
+    };
+
+    #[test]
+    fn test_objects() {
+        let patch = check(
+            json!({"a": 1, "b": {"c": [1, 2, 3]}, "d/e": true, "same": 1.0}),
+            json!({"a": 2, "b": {"c": [1, 3]}, "f~": null, "same": 1}),
+            DiffOptions::default(),
+        );
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "replace", "path": "/a", "value": 2},
+                {"op": "replace", "path": "/b/c/1", "value": 3},
+                {"op": "remove", "path": "/b/c/2"},
+                {"op": "remove", "path": "/d~1e"},
+                {"op": "add", "path": "/f~0", "value": null}
+            ])
+        );
+        assert_eq!(
+            check(json!([1]), json!({}), DiffOptions::default()).to_string(),
+            "[{\"op\":\"replace\",\"path\":\"\",\"value\":{}}]"
+        );
+        assert_eq!(
+            check(json!({"x": [1]}), json!({"x": [1]}), MOVES),
+            json!([])
+        );
+    }
+
+    #[test]
+    fn test_lcs_arrays() {
+        let patch = check(json!([1, 2, 3, 4, 5]), json!([1, 3, 4, 9, 5, 6]), LCS);
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "remove", "path": "/1"},
+                {"op": "add", "path": "/3", "value": 9},
+                {"op": "add", "path": "/5", "value": 6}
+            ])
+        );
+
+        // Elements that take each other's place are diffed instead of replaced
+        let patch = check(
+            json!([{"id": 1, "n": "a"}, {"id": 2}]),
+            json!([{"id": 1, "n": "b"}, {"id": 2}]),
+            LCS,
+        );
+        assert_eq!(
+            patch,
+            json!([{"op": "replace", "path": "/0/n", "value": "b"}])
+        );
+
+        for (from, to) in [
+            (json!([]), json!([1, 2])),
+            (json!([1, 2, 3]), json!([])),
+            (json!([1, 2, 3]), json!([3, 2, 1])),
+            (
+                json!(["a", "b", "c", "d"]),
+                json!(["x", "b", "y", "d", "z"]),
+            ),
+        ] {
+            check(from.clone(), to.clone(), LCS);
+            check(from, to, DiffOptions::default());
+        }
+    }
+
+    #[test]
+    fn test_moves() {
+        let patch = check(
+            json!({"draft": {"title": "t", "body": "long"}, "posts": []}),
+            json!({"posts": [{"title": "t", "body": "long"}]}),
+            MOVES,
+        );
+        assert_eq!(
+            patch,
+            json!([{"op": "move", "from": "/draft", "path": "/posts/0"}])
+        );
+
+        let patch = check(json!([1, 2, 3, 4]), json!([2, 3, 4, 1]), MOVES);
+        assert_eq!(patch, json!([{"op": "move", "from": "/0", "path": "/3"}]));
+        let patch = check(json!([1, 2, 3, 4]), json!([4, 1, 2, 3]), MOVES);
+        assert_eq!(patch, json!([{"op": "move", "from": "/3", "path": "/0"}]));
+
+        let patch = check(
+            json!({"a": {"x": 1}, "b": [5, 6]}),
+            json!({"b": [6, 5, {"x": 1}], "c": 2}),
+            MOVES,
+        );
+        assert_eq!(patch.as_array().unwrap().len(), 3);
+
+        // The destination is inside an element after the moved one
+        check(
+            json!([1, 5, 2, {"a": 0}]),
+            json!([1, 2, {"a": 0, "y": 5}]),
+            MOVES,
+        );
+        let patch = check(
+            json!({"x": null, "z": [false, "c~/", {}]}),
+            json!({"x": null, "z": ["c~/", {"q": false}]}),
+            MOVES,
+        );
+        assert_eq!(
+            patch,
+            json!([{"op": "move", "from": "/z/0", "path": "/z/1/q"}])
+        );
+
+        // All moves in the same array come from one diff
+        let from = Value::Array((0..200).map(Value::from).collect());
+        let to = Value::Array((0..200).rev().map(Value::from).collect());
+        let patch = check(from, to, MOVES);
+        let operations = patch.as_array().unwrap();
+        assert_eq!(operations.len(), 199);
+        assert!(operations
+            .iter()
+            .all(|op| op["op"].as_str() == Some("move")));
+
+        // Index by index arrays only have moves at their ends
+        check(
+            json!([1, 2, 3]),
+            json!([3, 1, 2]),
+            DiffOptions {
+                lcs_arrays: false,
+                detect_moves: true,
+            },
+        );
+    }
+}
@@ -0,0 +1,381 @@
+//! JSONPath queries (RFC 9535), enabled with the `jsonpath` feature.
+//!
+//! A query is compiled once and can then be run against any number of values:
+//!
+//! ```text
+//! let cheap = jsonlib::path::compile("$.store.book[?@.price < 10].title")?;
+//! for node in cheap.query(&store) {
+//!     println!("{} = {}", node.path(), node.value());  // $['store']['book'][0]['title'] = "..."
+//! }
+//! ```
+//!
+//! All of RFC 9535 is supported: names, wildcards, indices, slices, unions, descendants
+//! (`..`) and filters with comparisons, `&&`, `||`, `!` and the functions `length`, `count`,
+//! `match`, `search` and `value`. Patterns of `match` and `search` are I-Regexps (RFC 9485).
+
+mod ast;
+mod eval;
+mod function;
+mod parser;
+
+use crate::error::Error;
+use crate::value::Value;
+use std::fmt::{self, Write};
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// Compiles a JSONPath expression, failing with `Error::InvalidPath` when it does not
+/// follow the grammar or uses a function with the wrong arguments.
+pub fn compile(expression: &str) -> Result<JsonPath, Error> {
+    Ok(JsonPath {
+        expression: expression.to_string(),
+        query: parser::parse(expression)?,
+    })
+}
+
+/// A compiled JSONPath query.
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    expression: String,
+    query: ast::Query,
+}
+
+impl JsonPath {
+    /// The nodes the query selects in `value`, in the order RFC 9535 defines.
+    pub fn query<'a>(&self, value: &'a Value) -> Vec<Node<'a>> {
+        self.query.select(value, value, true)
+    }
+
+    /// Like `query`, without keeping track of the paths.
+    pub fn values<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        self.query
+            .select(value, value, false)
+            .into_iter()
+            .map(|node| node.value)
+            .collect()
+    }
+
+    /// The expression the query was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.expression
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        compile(expression)
+    }
+}
+
+/// A value selected by a query, along with where it is in the queried value.
+#[derive(Debug, Clone)]
+pub struct Node<'a> {
+    value: &'a Value,
+    location: Location<'a>,
+}
+
+impl<'a> Node<'a> {
+    pub fn value(&self) -> &'a Value {
+        self.value
+    }
+
+    /// The normalized path of the node, e.g. `$['store']['book'][0]`.
+    pub fn path(&self) -> String {
+        let mut steps = vec![];
+        let mut location = &self.location;
+        while let Some(link) = &location.0 {
+            steps.push(link.1);
+            location = &link.0;
+        }
+        let mut path = String::from("$");
+        for step in steps.into_iter().rev() {
+            match step {
+                Step::Member(key) => {
+                    path.push_str("['");
+                    escape_name(key, &mut path);
+                    path.push_str("']");
+                }
+                Step::Element(index) => write!(path, "[{}]", index).unwrap(),
+            }
+        }
+        path
+    }
+
+    fn root(value: &'a Value) -> Self {
+        Node {
+            value,
+            location: Location(None),
+        }
+    }
+
+    fn member(&self, key: &'a str, value: &'a Value, track: bool) -> Self {
+        Node {
+            value,
+            location: self.location.push(Step::Member(key), track),
+        }
+    }
+
+    fn element(&self, index: usize, value: &'a Value, track: bool) -> Self {
+        Node {
+            value,
+            location: self.location.push(Step::Element(index), track),
+        }
+    }
+}
+
+/// The steps from the root to a node, shared between the nodes below it.
+#[derive(Debug, Clone)]
+struct Location<'a>(Option<Rc<(Location<'a>, Step<'a>)>>);
+
+#[derive(Debug, Clone, Copy)]
+enum Step<'a> {
+    Member(&'a str),
+    Element(usize),
+}
+
+impl<'a> Location<'a> {
+    fn push(&self, step: Step<'a>, track: bool) -> Self {
+        match track {
+            true => Location(Some(Rc::new((self.clone(), step)))),
+            false => Location(None),
+        }
+    }
+}
+
+/// Writes a member name as a string in single quotes, as normalized paths do.
+fn escape_name(name: &str, out: &mut String) {
+    for c in name.chars() {
+        match c {
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            c if c < '\u{20}' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::error::Position;
+    use crate::json;
+
+    /// The example document of RFC 9535
+    fn store() -> Value {
+        json!({
+            "store": {
+                "book": [
+                    {"category": "reference", "author": "Nigel Rees",
+                     "title": "Sayings of the Century", "price": 8.95},
+                    {"category": "fiction", "author": "Evelyn Waugh",
+                     "title": "Sword of Honour", "price": 12.99},
+                    {"category": "fiction", "author": "Herman Melville",
+                     "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99},
+                    {"category": "fiction", "author": "J. R. R. Tolkien",
+                     "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99}
+                ],
+                "bicycle": {"color": "red", "price": 399}
+            }
+        })
+    }
+
+    fn paths(expression: &str, value: &Value) -> Vec<String> {
+        let query = compile(expression).unwrap();
+        query.query(value).iter().map(Node::path).collect()
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let store = store();
+        let values = |expression: &str| {
+            let found = compile(expression).unwrap().values(&store);
+            Value::Array(found.into_iter().cloned().collect())
+        };
+
+        assert_eq!(
+            values("$.store.book[*].author"),
+            json!([
+                "Nigel Rees",
+                "Evelyn Waugh",
+                "Herman Melville",
+                "J. R. R. Tolkien"
+            ])
+        );
+        assert_eq!(paths("$..author", &store).len(), 4);
+        assert_eq!(paths("$.store.*", &store).len(), 2);
+        assert_eq!(
+            values("$.store..price"),
+            json!([8.95, 12.99, 8.99, 22.99, 399])
+        );
+        assert_eq!(values("$..book[2].title"), json!(["Moby Dick"]));
+        assert_eq!(
+            values("$..book[-1].title"),
+            json!(["The Lord of the Rings"])
+        );
+        assert_eq!(values("$..book[0,1].title"), values("$..book[:2].title"));
+        assert_eq!(
+            values("$..book[?@.isbn].title"),
+            json!(["Moby Dick", "The Lord of the Rings"])
+        );
+        assert_eq!(
+            values("$..book[?@.price<10].title"),
+            json!(["Sayings of the Century", "Moby Dick"])
+        );
+        assert_eq!(paths("$..*", &store).len(), 27);
+        assert_eq!(
+            paths("$.store.book[?@.price < 10].title", &store),
+            vec![
+                "$['store']['book'][0]['title']",
+                "$['store']['book'][2]['title']"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selectors() {
+        let value = json!({"a": [0, 1, 2, 3, 4, 5], "o": {"j": 1, "k": 2}, "it's": true});
+        assert_eq!(paths("$['it\\'s']", &value), vec!["$['it\\'s']"]);
+        assert_eq!(
+            paths("$[\"o\"]['k', 'j', 'x']", &value),
+            vec!["$['o']['k']", "$['o']['j']"]
+        );
+        let found = compile("$.a[1:5:2, -1, ::-3]").unwrap().values(&value);
+        assert_eq!(
+            found,
+            [&json!(1), &json!(3), &json!(5), &json!(5), &json!(2)]
+        );
+        assert_eq!(paths("$.a[7]", &value), Vec::<String>::new());
+        assert_eq!(paths("$.o[0]", &value), Vec::<String>::new());
+        assert_eq!(paths("$", &value), vec!["$"]);
+        assert_eq!(
+            paths("$..[0]", &json!([[1], {"x": [2]}])),
+            vec!["$[0]", "$[0][0]", "$[1]['x'][0]"]
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let value = json!([
+            {"a": 1, "b": "x"},
+            {"a": 1.0, "b": [1, 2]},
+            {"a": 3, "b": {"c": null}},
+            {"b": "y"},
+            5
+        ]);
+        let indices = |expression: &str| -> Vec<String> { paths(expression, &value) };
+        assert_eq!(indices("$[?@.a == 1]"), vec!["$[0]", "$[1]"]);
+        assert_eq!(indices("$[?@.a != 1]"), vec!["$[2]", "$[3]", "$[4]"]);
+        assert_eq!(indices("$[?@.a >= 1 && @.a < 3]"), vec!["$[0]", "$[1]"]);
+        assert_eq!(
+            indices("$[?!@.a || @.b == 'x']"),
+            vec!["$[0]", "$[3]", "$[4]"]
+        );
+        assert_eq!(
+            indices("$[?@.b == 'y' || !(@.a <= 1)]"),
+            vec!["$[2]", "$[3]", "$[4]"]
+        );
+        assert_eq!(indices("$[?@.b.c == null]"), vec!["$[2]"]);
+        assert_eq!(indices("$[?@.missing == @.other]").len(), 5);
+        assert_eq!(indices("$[?@ == 5]"), vec!["$[4]"]);
+        assert_eq!(indices("$[?@.b > 'x']"), vec!["$[3]"]);
+        assert_eq!(indices("$[?@.b == $[1].b]"), vec!["$[1]"]);
+        assert_eq!(indices("$[?@.b[?@ == 2]]"), vec!["$[1]"]);
+    }
+
+    #[test]
+    fn test_functions() {
+        let value = json!([
+            {"name": "alpha", "tags": ["a", "b"]},
+            {"name": "Beta", "tags": []},
+            {"name": "gamma-ray", "tags": ["c"]}
+        ]);
+        let indices = |expression: &str| -> Vec<String> { paths(expression, &value) };
+        assert_eq!(indices("$[?length(@.name) == 5]"), vec!["$[0]"]);
+        assert_eq!(indices("$[?count(@.tags[*]) > 0]"), vec!["$[0]", "$[2]"]);
+        assert_eq!(indices("$[?match(@.name, '[a-z]+')]"), vec!["$[0]"]);
+        assert_eq!(indices("$[?search(@.name, '-r')]"), vec!["$[2]"]);
+        assert_eq!(indices("$[?!search(@.name, 'a')]").len(), 0);
+        assert_eq!(indices("$[?match(@.name, $[1].name)]"), vec!["$[1]"]);
+        assert_eq!(indices("$[?value(@.tags[*]) == 'c']"), vec!["$[2]"]);
+        assert_eq!(indices("$[?length(@.tags) == count(@.tags.*)]").len(), 3);
+        assert_eq!(indices("$[?match(@.name, '(')]").len(), 0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |expression: &str| compile(expression).unwrap_err();
+
+        assert_eq!(
+            error("$.store[?@.price < 10 ]]"),
+            Error::InvalidPath {
+                reason: "unexpected content after the query",
+                position: Position {
+                    line: 1,
+                    column: 24,
+                    offset: 23
+                },
+            }
+        );
+        assert_eq!(
+            error("$[?@.* == 1]").to_string(),
+            "Invalid JSONPath, expected a literal, a singular query or a function returning \
+             a value at line 1, column 4"
+        );
+        for (expression, reason) in [
+            ("store", "a JSONPath query has to start with '$'"),
+            ("$.store ", "unexpected content after the query"),
+            ("$. store", "expected a member name or '*' after '.'"),
+            ("$[1 2]", "expected ',' or ']'"),
+            ("$[01]", "integers can not have leading zeros or be -0"),
+            ("$[9007199254740992]", "the integer is out of range"),
+            ("$['a\\x']", "invalid escape sequence"),
+            ("$['a", "the string is never closed"),
+            ("$[?@.a == 1", "expected ',' or ']'"),
+            ("$[?1]", "a literal has to be compared with something"),
+            ("$[?(@.a]", "expected ')'"),
+            (
+                "$[?length(@.a)]",
+                "the value of this function has to be compared",
+            ),
+            ("$[?match(@.a, 'x') == true]", VALUE_ERROR),
+            ("$[?foo(@.a)]", "unknown function"),
+            (
+                "$[?count(@.a, 1) > 0]",
+                "wrong number of arguments for this function",
+            ),
+            ("$[?count(1) > 0]", "expected a query"),
+            ("$[?length(@..a) > 0]", VALUE_ERROR),
+        ] {
+            match compile(expression) {
+                Err(Error::InvalidPath { reason: found, .. }) => {
+                    assert_eq!(found, reason, "{}", expression)
+                }
+                other => panic!("{} compiled to {:?}", expression, other),
+            }
+        }
+    }
+
+    const VALUE_ERROR: &str =
+        "expected a literal, a singular query or a function returning a value";
+
+    #[test]
+    fn test_normalized_paths() {
+        let value = json!({"a'b\\c\u{1}": [true]});
+        assert_eq!(paths("$.*[0]", &value), vec!["$['a\\'b\\\\c\\u0001'][0]"]);
+        let query: JsonPath = "$ ['x']".parse().unwrap();
+        assert_eq!(query.to_string(), "$ ['x']");
+    }
+}
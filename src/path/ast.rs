@@ -0,0 +1,94 @@
+use super::function::Function;
+use crate::value::Value;
+use regex::Regex;
+
+/// `$` or `@` followed by segments.
+#[derive(Debug, Clone)]
+pub(super) struct Query {
+    /// Starts at `@`, the node a filter is looking at, instead of the root
+    pub relative: bool,
+    pub segments: Vec<Segment>,
+}
+
+impl Query {
+    /// A query that selects at most one node: one name or index per segment and no `..`.
+    pub fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            !segment.descendant
+                && matches!(
+                    segment.selectors[..],
+                    [Selector::Name(_)] | [Selector::Index(_)]
+                )
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Segment {
+    /// `..`, the selectors apply to the node and everything below it
+    pub descendant: bool,
+    pub selectors: Vec<Selector>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Selector {
+    Name(String),
+    Wildcard,
+    /// Counts from the end when negative
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Expr),
+}
+
+/// A logical expression in a filter.
+#[derive(Debug, Clone)]
+pub(super) enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Compare(Comparable, CompareOp, Comparable),
+    /// True when the query selects anything
+    Exists(Query),
+    /// A function returning a logical value or nodes
+    Call(Call),
+}
+
+/// One side of a comparison.
+#[derive(Debug, Clone)]
+pub(super) enum Comparable {
+    Literal(Value),
+    /// Always singular
+    Query(Query),
+    /// Always returning a value
+    Call(Call),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Call {
+    pub function: Function,
+    /// Checked against the parameters of the function
+    pub args: Vec<Argument>,
+    /// The pattern of `match` or `search`, compiled ahead when it is a string literal
+    pub pattern: Option<Regex>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Argument {
+    Value(Comparable),
+    Nodes(Query),
+    Logical(Expr),
+}
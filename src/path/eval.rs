@@ -0,0 +1,279 @@
+use super::ast::{Argument, Call, Comparable, CompareOp, Expr, Query, Segment, Selector};
+use super::function::{self, Function};
+use super::Node;
+use crate::value::Value;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+/// The result of a function argument or call.
+enum Evaluated<'a> {
+    /// `None` is nothing, e.g. a singular query that selected no node
+    Value(Option<Cow<'a, Value>>),
+    Logical(bool),
+    Nodes(Vec<&'a Value>),
+}
+
+impl Query {
+    /// The nodes the query selects, starting at `root` or at `current` for `@`. Without
+    /// `track` the nodes do not keep their locations.
+    pub(super) fn select<'a>(
+        &self,
+        root: &'a Value,
+        current: &'a Value,
+        track: bool,
+    ) -> Vec<Node<'a>> {
+        let start = if self.relative { current } else { root };
+        let mut nodes = vec![Node::root(start)];
+        for segment in &self.segments {
+            let mut next = vec![];
+            for node in &nodes {
+                if segment.descendant {
+                    segment.descend(root, node, track, &mut next);
+                } else {
+                    segment.apply(root, node, track, &mut next);
+                }
+            }
+            nodes = next;
+        }
+        nodes
+    }
+}
+
+impl Segment {
+    fn apply<'a>(&self, root: &'a Value, node: &Node<'a>, track: bool, out: &mut Vec<Node<'a>>) {
+        for selector in &self.selectors {
+            selector.select(root, node, track, out);
+        }
+    }
+
+    /// Applies the selectors to `node` and then to everything below it, in document order.
+    fn descend<'a>(&self, root: &'a Value, node: &Node<'a>, track: bool, out: &mut Vec<Node<'a>>) {
+        self.apply(root, node, track, out);
+        for child in children(node, track) {
+            self.descend(root, &child, track, out);
+        }
+    }
+}
+
+fn children<'a>(node: &Node<'a>, track: bool) -> Vec<Node<'a>> {
+    match node.value {
+        Value::Object(obj) => obj
+            .iter()
+            .map(|(key, value)| node.member(key, value, track))
+            .collect(),
+        Value::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .map(|(index, value)| node.element(index, value, track))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+impl Selector {
+    fn select<'a>(&self, root: &'a Value, node: &Node<'a>, track: bool, out: &mut Vec<Node<'a>>) {
+        match (self, node.value) {
+            (Selector::Name(name), Value::Object(obj)) => {
+                if let Some((key, value)) = obj.get_key_value(name) {
+                    out.push(node.member(key, value, track));
+                }
+            }
+            (Selector::Wildcard, _) => out.extend(children(node, track)),
+            (Selector::Index(index), Value::Array(arr)) => {
+                let len = arr.len() as i64;
+                let index = if *index < 0 { len + index } else { *index };
+                if (0..len).contains(&index) {
+                    let index = index as usize;
+                    out.push(node.element(index, &arr[index], track));
+                }
+            }
+            (Selector::Slice { start, end, step }, Value::Array(arr)) => {
+                for index in slice_indices(arr.len() as i64, *start, *end, *step) {
+                    let index = index as usize;
+                    out.push(node.element(index, &arr[index], track));
+                }
+            }
+            (Selector::Filter(expr), Value::Object(_) | Value::Array(_)) => out.extend(
+                children(node, track)
+                    .into_iter()
+                    .filter(|child| expr.test(child.value, root)),
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// The indices a slice selects in an array of `len` elements, RFC 9535 section 2.3.4.2.
+fn slice_indices(len: i64, start: Option<i64>, end: Option<i64>, step: Option<i64>) -> Vec<i64> {
+    let step = step.unwrap_or(1);
+    let normalize = |index: i64| if index < 0 { len + index } else { index };
+    let mut indices = vec![];
+    match step.cmp(&0) {
+        Ordering::Equal => {}
+        Ordering::Greater => {
+            let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+            let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+            let mut index = lower;
+            while index < upper {
+                indices.push(index);
+                index += step;
+            }
+        }
+        Ordering::Less => {
+            let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+            let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+            let mut index = upper;
+            while lower < index {
+                indices.push(index);
+                index += step;
+            }
+        }
+    }
+    indices
+}
+
+impl Expr {
+    /// Whether the filter keeps `current`.
+    fn test(&self, current: &Value, root: &Value) -> bool {
+        match self {
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.test(current, root)),
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.test(current, root)),
+            Expr::Not(expr) => !expr.test(current, root),
+            Expr::Compare(left, op, right) => compare(
+                left.evaluate(current, root).as_deref(),
+                *op,
+                right.evaluate(current, root).as_deref(),
+            ),
+            Expr::Exists(query) => !query.select(root, current, false).is_empty(),
+            Expr::Call(call) => match call.evaluate(current, root) {
+                Evaluated::Logical(result) => result,
+                Evaluated::Nodes(nodes) => !nodes.is_empty(),
+                // Rejected when parsing
+                Evaluated::Value(_) => false,
+            },
+        }
+    }
+}
+
+impl Comparable {
+    fn evaluate<'a>(&'a self, current: &'a Value, root: &'a Value) -> Option<Cow<'a, Value>> {
+        match self {
+            Comparable::Literal(value) => Some(Cow::Borrowed(value)),
+            Comparable::Query(query) => query
+                .select(root, current, false)
+                .first()
+                .map(|node| Cow::Borrowed(node.value)),
+            Comparable::Call(call) => match call.evaluate(current, root) {
+                Evaluated::Value(value) => value,
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Call {
+    fn evaluate<'a>(&'a self, current: &'a Value, root: &'a Value) -> Evaluated<'a> {
+        let mut args = self.args.iter().map(|arg| match arg {
+            Argument::Value(comparable) => Evaluated::Value(comparable.evaluate(current, root)),
+            Argument::Nodes(query) => Evaluated::Nodes(
+                query
+                    .select(root, current, false)
+                    .into_iter()
+                    .map(|node| node.value)
+                    .collect(),
+            ),
+            Argument::Logical(expr) => Evaluated::Logical(expr.test(current, root)),
+        });
+        // The arguments have been checked against the parameters when parsing
+        let (first, second) = (args.next(), args.next());
+        match (self.function, first, second) {
+            (Function::Length, Some(Evaluated::Value(value)), _) => Evaluated::Value(
+                value
+                    .as_deref()
+                    .and_then(function::length)
+                    .map(|len| Cow::Owned(len.into())),
+            ),
+            (Function::Count, Some(Evaluated::Nodes(nodes)), _) => {
+                Evaluated::Value(Some(Cow::Owned(nodes.len().into())))
+            }
+            (Function::Value, Some(Evaluated::Nodes(nodes)), _) => {
+                Evaluated::Value(match nodes[..] {
+                    [value] => Some(Cow::Borrowed(value)),
+                    _ => None,
+                })
+            }
+            (
+                Function::Match | Function::Search,
+                Some(Evaluated::Value(text)),
+                Some(Evaluated::Value(pattern)),
+            ) => {
+                let matched = match (text.as_deref(), pattern.as_deref()) {
+                    (Some(Value::String(text)), Some(Value::String(pattern))) => {
+                        match &self.pattern {
+                            Some(regex) => regex.is_match(text),
+                            None => self
+                                .function
+                                .regex(pattern)
+                                .is_some_and(|regex| regex.is_match(text)),
+                        }
+                    }
+                    _ => false,
+                };
+                Evaluated::Logical(matched)
+            }
+            _ => Evaluated::Logical(false),
+        }
+    }
+}
+
+fn compare(left: Option<&Value>, op: CompareOp, right: Option<&Value>) -> bool {
+    match op {
+        CompareOp::Eq => equal(left, right),
+        CompareOp::Ne => !equal(left, right),
+        CompareOp::Lt => less(left, right),
+        CompareOp::Le => less(left, right) || equal(left, right),
+        CompareOp::Gt => less(right, left),
+        CompareOp::Ge => less(right, left) || equal(left, right),
+    }
+}
+
+/// Nothing only equals nothing.
+fn equal(left: Option<&Value>, right: Option<&Value>) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(left), Some(right)) => left.json_eq(right),
+        _ => false,
+    }
+}
+
+/// Only numbers and strings can be ordered.
+fn less(left: Option<&Value>, right: Option<&Value>) -> bool {
+    match (left, right) {
+        (Some(Value::Number(left)), Some(Value::Number(right))) => {
+            left.compare(right) == Some(Ordering::Less)
+        }
+        // Byte order of UTF-8 is the order of the code points
+        (Some(Value::String(left)), Some(Value::String(right))) => left < right,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_slice_indices() {
+        assert_eq!(slice_indices(5, Some(1), Some(3), None), vec![1, 2]);
+        assert_eq!(slice_indices(5, Some(-2), None, None), vec![3, 4]);
+        assert_eq!(slice_indices(5, None, None, Some(2)), vec![0, 2, 4]);
+        assert_eq!(slice_indices(5, None, None, Some(-1)), vec![4, 3, 2, 1, 0]);
+        assert_eq!(slice_indices(5, Some(3), Some(0), Some(-2)), vec![3, 1]);
+        assert_eq!(
+            slice_indices(5, Some(-10), Some(10), None),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert_eq!(slice_indices(5, None, None, Some(0)), Vec::<i64>::new());
+        assert_eq!(slice_indices(0, None, None, Some(-1)), Vec::<i64>::new());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::value::Value;
+use regex::Regex;
+
+/// The function extensions of RFC 9535, section 2.4.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+/// The types of function parameters and results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Type {
+    /// A JSON value or nothing
+    Value,
+    Logical,
+    /// The nodes a query selected
+    Nodes,
+}
+
+impl Function {
+    pub fn from_name(name: &str) -> Option<Function> {
+        match name {
+            "length" => Some(Function::Length),
+            "count" => Some(Function::Count),
+            "match" => Some(Function::Match),
+            "search" => Some(Function::Search),
+            "value" => Some(Function::Value),
+            _ => None,
+        }
+    }
+
+    pub fn parameters(self) -> &'static [Type] {
+        match self {
+            Function::Length => &[Type::Value],
+            Function::Count | Function::Value => &[Type::Nodes],
+            Function::Match | Function::Search => &[Type::Value, Type::Value],
+        }
+    }
+
+    pub fn result(self) -> Type {
+        match self {
+            Function::Length | Function::Count | Function::Value => Type::Value,
+            Function::Match | Function::Search => Type::Logical,
+        }
+    }
+
+    /// Compiles the pattern of `match`, which has to match the whole string, or `search`,
+    /// which looks for it anywhere. `None` if the pattern is not a valid I-Regexp.
+    pub fn regex(self, pattern: &str) -> Option<Regex> {
+        let translated = translate(pattern)?;
+        match self {
+            Function::Match => Regex::new(&format!("^(?:{})$", translated)).ok(),
+            _ => Regex::new(&translated).ok(),
+        }
+    }
+}
+
+/// The length of a string in characters, or the number of elements or members.
+pub(super) fn length(value: &Value) -> Option<usize> {
+    match value {
+        Value::String(string) => Some(string.chars().count()),
+        Value::Array(arr) => Some(arr.len()),
+        Value::Object(obj) => Some(obj.len()),
+        _ => None,
+    }
+}
+
+/// Rewrites an I-Regexp (RFC 9485) in the syntax of the regex crate, rejecting what
+/// I-Regexp does not have, like anchors, groups with flags and shorthands like `\d`.
+fn translate(pattern: &str) -> Option<String> {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped = chars.next()?;
+                match escaped {
+                    'p' | 'P' => {
+                        out.push('\\');
+                        out.push(escaped);
+                        if chars.next()? != '{' {
+                            return None;
+                        }
+                        out.push('{');
+                        loop {
+                            let c = chars.next()?;
+                            out.push(c);
+                            if c == '}' {
+                                break;
+                            }
+                        }
+                    }
+                    'n' | 'r' | 't' | '(' | ')' | '*' | '+' | '-' | '.' | '?' | '[' | '\\'
+                    | ']' | '^' | '{' | '|' | '}' => {
+                        out.push('\\');
+                        out.push(escaped);
+                    }
+                    _ => return None,
+                }
+            }
+            '[' if in_class => return None,
+            '[' => {
+                in_class = true;
+                out.push('[');
+                if chars.peek() == Some(&'^') {
+                    chars.next();
+                    out.push('^');
+                }
+            }
+            ']' if in_class => {
+                in_class = false;
+                out.push(']');
+            }
+            // Any character but line breaks
+            '.' if !in_class => out.push_str("[^\\n\\r]"),
+            // Not anchors in I-Regexp, and `&&` and `~~` are set operations in classes here
+            '^' | '$' if !in_class => {
+                out.push('\\');
+                out.push(c);
+            }
+            '&' | '~' if in_class => {
+                out.push('\\');
+                out.push(c);
+            }
+            '(' if chars.peek() == Some(&'?') => return None,
+            c => out.push(c),
+        }
+    }
+    if in_class {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_regex() {
+        let matches = |function: Function, pattern: &str, text: &str| {
+            function.regex(pattern).map(|regex| regex.is_match(text))
+        };
+        assert_eq!(matches(Function::Match, "a.c", "abc"), Some(true));
+        assert_eq!(matches(Function::Match, "a.c", "a\nc"), Some(false));
+        assert_eq!(matches(Function::Match, "b", "abc"), Some(false));
+        assert_eq!(matches(Function::Search, "b", "abc"), Some(true));
+        assert_eq!(matches(Function::Search, "^b", "a^b"), Some(true));
+        assert_eq!(
+            matches(Function::Match, "\\p{Lu}+[0-9]?", "ÀB1"),
+            Some(true)
+        );
+        assert_eq!(matches(Function::Match, "[a&&b]+", "a&&b"), Some(true));
+
+        for invalid in ["\\d", "(?i)a", "[a", "a(", "\\p{Foo}", "\\"] {
+            assert!(Function::Search.regex(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(length(&Value::from("é🦀")), Some(2));
+        assert_eq!(length(&Value::from(vec![1, 2])), Some(2));
+        assert_eq!(length(&Value::from(3)), None);
+    }
+}
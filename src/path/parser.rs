@@ -0,0 +1,536 @@
+use super::ast::{Argument, Call, Comparable, CompareOp, Expr, Query, Segment, Selector};
+use super::function::{Function, Type};
+use crate::error::{Error, Position};
+use crate::number::Number;
+use crate::value::Value;
+
+/// Indices and slice bounds have to be exact in a double, like integers in I-JSON
+const MAX_INT: i64 = (1 << 53) - 1;
+
+const VALUE_EXPECTED: &str = "expected a literal, a singular query or a function returning a value";
+
+/// Parses a whole JSONPath expression following the grammar of RFC 9535.
+pub(super) fn parse(expression: &str) -> Result<Query, Error> {
+    let mut parser = Parser {
+        input: expression,
+        pos: 0,
+    };
+    if parser.peek() != Some('$') {
+        return Err(parser.error("a JSONPath query has to start with '$'"));
+    }
+    let query = parser.query()?;
+    if parser.pos < expression.len() {
+        return Err(parser.error("unexpected content after the query"));
+    }
+    Ok(query)
+}
+
+/// What can stand on either side of a comparison or on its own in a filter, before
+/// knowing which of the two it is.
+enum Operand {
+    Literal(Value),
+    Query(Query),
+    Call(Call),
+}
+
+struct Parser<'s> {
+    input: &'s str,
+    /// Byte offset of the next character
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    /// Consumes `text` if the input continues with it.
+    fn eat(&mut self, text: &str) -> bool {
+        if self.input[self.pos..].starts_with(text) {
+            self.pos += text.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_digits(&mut self) -> &'s str {
+        let start = self.pos;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.pos += 1;
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn error(&self, reason: &'static str) -> Error {
+        self.error_at(self.pos, reason)
+    }
+
+    fn error_at(&self, offset: usize, reason: &'static str) -> Error {
+        let before = &self.input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Error::InvalidPath {
+            reason,
+            position: Position {
+                line: before.matches('\n').count() + 1,
+                column: before[line_start..].chars().count() + 1,
+                offset,
+            },
+        }
+    }
+
+    /// A query starting at the `$` or `@` under the cursor.
+    fn query(&mut self) -> Result<Query, Error> {
+        let relative = self.peek() == Some('@');
+        self.pos += 1;
+        Ok(Query {
+            relative,
+            segments: self.segments()?,
+        })
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, Error> {
+        let mut segments = vec![];
+        loop {
+            // Blanks are only allowed between segments, not after the last one
+            let start = self.pos;
+            self.skip_blank();
+            let segment = if self.eat("..") {
+                let selectors = if self.peek() == Some('[') {
+                    self.bracketed()?
+                } else {
+                    vec![self.shorthand("expected a member name, '*' or '[' after '..'")?]
+                };
+                Segment {
+                    descendant: true,
+                    selectors,
+                }
+            } else if self.eat(".") {
+                Segment {
+                    descendant: false,
+                    selectors: vec![self.shorthand("expected a member name or '*' after '.'")?],
+                }
+            } else if self.peek() == Some('[') {
+                Segment {
+                    descendant: false,
+                    selectors: self.bracketed()?,
+                }
+            } else {
+                self.pos = start;
+                return Ok(segments);
+            };
+            segments.push(segment);
+        }
+    }
+
+    /// `*` or a member name written without brackets and quotes.
+    fn shorthand(&mut self, reason: &'static str) -> Result<Selector, Error> {
+        if self.eat("*") {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if is_name_first(c) => {}
+            _ => return Err(self.error(reason)),
+        }
+        while let Some(c) = self.peek() {
+            if !is_name_first(c) && !c.is_ascii_digit() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        Ok(Selector::Name(self.input[start..self.pos].to_string()))
+    }
+
+    /// The selectors between `[` and `]`.
+    fn bracketed(&mut self) -> Result<Vec<Selector>, Error> {
+        self.pos += 1;
+        let mut selectors = vec![];
+        loop {
+            self.skip_blank();
+            selectors.push(self.selector()?);
+            self.skip_blank();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected ',' or ']'"));
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, Error> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.string()?)),
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.pos += 1;
+                self.skip_blank();
+                Ok(Selector::Filter(self.logical()?))
+            }
+            Some(':' | '-' | '0'..='9') => self.index_or_slice(),
+            _ => Err(self.error("expected a selector")),
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, Error> {
+        let start = self.integer()?;
+        self.skip_blank();
+        if !self.eat(":") {
+            return match start {
+                Some(index) => Ok(Selector::Index(index)),
+                None => Err(self.error("expected a selector")),
+            };
+        }
+        self.skip_blank();
+        let end = self.integer()?;
+        self.skip_blank();
+        let step = if self.eat(":") {
+            self.skip_blank();
+            self.integer()?
+        } else {
+            None
+        };
+        Ok(Selector::Slice { start, end, step })
+    }
+
+    /// An index or slice bound, if there is one under the cursor.
+    fn integer(&mut self) -> Result<Option<i64>, Error> {
+        let start = self.pos;
+        let negative = self.eat("-");
+        let digits = self.skip_digits();
+        if digits.is_empty() {
+            if negative {
+                return Err(self.error_at(start, "expected digits after '-'"));
+            }
+            return Ok(None);
+        }
+        if (digits.len() > 1 && digits.starts_with('0')) || (negative && digits == "0") {
+            return Err(self.error_at(start, "integers can not have leading zeros or be -0"));
+        }
+        match self.input[start..self.pos].parse::<i64>() {
+            Ok(num) if num.abs() <= MAX_INT => Ok(Some(num)),
+            _ => Err(self.error_at(start, "the integer is out of range")),
+        }
+    }
+
+    /// A string literal in single or double quotes.
+    fn string(&mut self) -> Result<String, Error> {
+        let start = self.pos;
+        let quote = self.peek();
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error_at(start, "the string is never closed")),
+            };
+            match c {
+                '\\' => out.push(self.escape(quote)?),
+                '\u{0}'..='\u{1f}' => {
+                    return Err(self.error("control characters in strings have to be escaped"))
+                }
+                c if Some(c) == quote => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                c => {
+                    self.pos += c.len_utf8();
+                    out.push(c);
+                }
+            }
+        }
+    }
+
+    /// An escape sequence starting at the backslash under the cursor.
+    fn escape(&mut self, quote: Option<char>) -> Result<char, Error> {
+        let start = self.pos;
+        self.pos += 1;
+        let c = self.peek();
+        self.pos += c.map_or(0, char::len_utf8);
+        let unescaped = match c {
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some(c @ ('/' | '\\')) => c,
+            Some(c) if Some(c) == quote => c,
+            Some('u') => {
+                let high = self.hex4(start)?;
+                let code = match high {
+                    0xD800..=0xDBFF => {
+                        if !self.eat("\\u") {
+                            return Err(self.error_at(start, "unpaired surrogate"));
+                        }
+                        match self.hex4(start)? {
+                            low @ 0xDC00..=0xDFFF => {
+                                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                            }
+                            _ => return Err(self.error_at(start, "unpaired surrogate")),
+                        }
+                    }
+                    0xDC00..=0xDFFF => return Err(self.error_at(start, "unpaired surrogate")),
+                    code => code,
+                };
+                // Surrogates are ruled out above
+                char::from_u32(code).unwrap()
+            }
+            _ => return Err(self.error_at(start, "invalid escape sequence")),
+        };
+        Ok(unescaped)
+    }
+
+    fn hex4(&mut self, start: usize) -> Result<u32, Error> {
+        let hex = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error_at(start, "invalid escape sequence"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(hex, 16).unwrap())
+    }
+
+    fn number(&mut self) -> Result<Value, Error> {
+        let start = self.pos;
+        self.eat("-");
+        let int = self.skip_digits();
+        if int.is_empty() || (int.len() > 1 && int.starts_with('0')) {
+            return Err(self.error_at(start, "invalid number"));
+        }
+        if self.eat(".") && self.skip_digits().is_empty() {
+            return Err(self.error_at(start, "invalid number"));
+        }
+        if self.eat("e") || self.eat("E") {
+            if !self.eat("+") {
+                self.eat("-");
+            }
+            if self.skip_digits().is_empty() {
+                return Err(self.error_at(start, "invalid number"));
+            }
+        }
+        Ok(Value::Number(Number::from_literal(
+            &self.input[start..self.pos],
+        )))
+    }
+
+    /// `a || b`, the loosest binding logical expression.
+    fn logical(&mut self) -> Result<Expr, Error> {
+        let mut operands = vec![self.and()?];
+        while self.eat_operator("||") {
+            operands.push(self.and()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.pop().unwrap(),
+            _ => Expr::Or(operands),
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut operands = vec![self.basic()?];
+        while self.eat_operator("&&") {
+            operands.push(self.basic()?);
+        }
+        Ok(match operands.len() {
+            1 => operands.pop().unwrap(),
+            _ => Expr::And(operands),
+        })
+    }
+
+    /// Consumes `operator` and the blanks around it, or nothing.
+    fn eat_operator(&mut self, operator: &str) -> bool {
+        let start = self.pos;
+        self.skip_blank();
+        if self.eat(operator) {
+            self.skip_blank();
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    /// A parenthesized expression, a comparison or a test, each possibly negated.
+    fn basic(&mut self) -> Result<Expr, Error> {
+        if self.eat("!") {
+            self.skip_blank();
+            let start = self.pos;
+            let expr = if self.eat("(") {
+                self.parenthesized()?
+            } else {
+                match self.operand()? {
+                    Operand::Query(query) => Expr::Exists(query),
+                    Operand::Call(call) => self.test_call(call, start)?,
+                    Operand::Literal(_) => {
+                        return Err(self.error_at(start, "expected a query, a function or '('"))
+                    }
+                }
+            };
+            return Ok(Expr::Not(Box::new(expr)));
+        }
+        if self.eat("(") {
+            return self.parenthesized();
+        }
+
+        let start = self.pos;
+        let left = self.operand()?;
+        let before_op = self.pos;
+        self.skip_blank();
+        let op = match self.comparison_op() {
+            Some(op) => op,
+            None => {
+                self.pos = before_op;
+                return match left {
+                    Operand::Query(query) => Ok(Expr::Exists(query)),
+                    Operand::Call(call) => self.test_call(call, start),
+                    Operand::Literal(_) => {
+                        Err(self.error_at(start, "a literal has to be compared with something"))
+                    }
+                };
+            }
+        };
+        let left = self.comparable(left, start)?;
+        self.skip_blank();
+        let start = self.pos;
+        let right = self.operand()?;
+        let right = self.comparable(right, start)?;
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    /// The rest of an expression after `(`.
+    fn parenthesized(&mut self) -> Result<Expr, Error> {
+        self.skip_blank();
+        let expr = self.logical()?;
+        self.skip_blank();
+        if !self.eat(")") {
+            return Err(self.error("expected ')'"));
+        }
+        Ok(expr)
+    }
+
+    fn comparison_op(&mut self) -> Option<CompareOp> {
+        let ops = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+        ops.into_iter()
+            .find(|(text, _)| self.eat(text))
+            .map(|(_, op)| op)
+    }
+
+    fn operand(&mut self) -> Result<Operand, Error> {
+        match self.peek() {
+            Some('$' | '@') => Ok(Operand::Query(self.query()?)),
+            Some('\'' | '"') => Ok(Operand::Literal(Value::String(self.string()?))),
+            Some('-' | '0'..='9') => Ok(Operand::Literal(self.number()?)),
+            Some('a'..='z') => {
+                let start = self.pos;
+                while matches!(self.peek(), Some('a'..='z' | '0'..='9' | '_')) {
+                    self.pos += 1;
+                }
+                let input = self.input;
+                let name = &input[start..self.pos];
+                if self.peek() == Some('(') {
+                    return Ok(Operand::Call(self.call(name, start)?));
+                }
+                match name {
+                    "true" => Ok(Operand::Literal(Value::Bool(true))),
+                    "false" => Ok(Operand::Literal(Value::Bool(false))),
+                    "null" => Ok(Operand::Literal(Value::Null)),
+                    _ => Err(self.error_at(start, "expected a literal, a query or a function")),
+                }
+            }
+            _ => Err(self.error("expected a literal, a query or a function")),
+        }
+    }
+
+    fn comparable(&self, operand: Operand, start: usize) -> Result<Comparable, Error> {
+        match operand {
+            Operand::Literal(value) => Ok(Comparable::Literal(value)),
+            Operand::Query(query) if query.is_singular() => Ok(Comparable::Query(query)),
+            Operand::Call(call) if call.function.result() == Type::Value => {
+                Ok(Comparable::Call(call))
+            }
+            _ => Err(self.error_at(start, VALUE_EXPECTED)),
+        }
+    }
+
+    /// A function used as a test on its own has to return a logical value or nodes.
+    fn test_call(&self, call: Call, start: usize) -> Result<Expr, Error> {
+        if call.function.result() == Type::Value {
+            return Err(self.error_at(start, "the value of this function has to be compared"));
+        }
+        Ok(Expr::Call(call))
+    }
+
+    fn wrong_arity(&self, start: usize) -> Error {
+        self.error_at(start, "wrong number of arguments for this function")
+    }
+
+    /// A function call, with the cursor on the `(` after `name`.
+    fn call(&mut self, name: &str, start: usize) -> Result<Call, Error> {
+        let function =
+            Function::from_name(name).ok_or_else(|| self.error_at(start, "unknown function"))?;
+        let parameters = function.parameters();
+        self.pos += 1;
+        self.skip_blank();
+        let mut args = vec![];
+        if !self.eat(")") {
+            loop {
+                let arg_start = self.pos;
+                let arg = match parameters.get(args.len()) {
+                    Some(Type::Value) => {
+                        let operand = self.operand()?;
+                        Argument::Value(self.comparable(operand, arg_start)?)
+                    }
+                    Some(Type::Nodes) => match self.operand()? {
+                        Operand::Query(query) => Argument::Nodes(query),
+                        _ => return Err(self.error_at(arg_start, "expected a query")),
+                    },
+                    Some(Type::Logical) => Argument::Logical(self.logical()?),
+                    None => return Err(self.wrong_arity(start)),
+                };
+                args.push(arg);
+                self.skip_blank();
+                if self.eat(")") {
+                    break;
+                }
+                if !self.eat(",") {
+                    return Err(self.error("expected ',' or ')'"));
+                }
+                self.skip_blank();
+            }
+        }
+        if args.len() != parameters.len() {
+            return Err(self.wrong_arity(start));
+        }
+        let pattern = match args.get(1) {
+            Some(Argument::Value(Comparable::Literal(Value::String(pattern)))) => {
+                function.regex(pattern)
+            }
+            _ => None,
+        };
+        Ok(Call {
+            function,
+            args,
+            pattern,
+        })
+    }
+}
+
+fn is_name_first(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
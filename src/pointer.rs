@@ -0,0 +1,223 @@
+use crate::error::Error;
+use crate::value::Value;
+use std::mem;
+
+/// Splits an RFC 6901 JSON Pointer like `/a~1b/0` into its unescaped reference tokens,
+/// here `a/b` and `0`. The empty pointer has no tokens and refers to the whole document.
+pub(crate) fn parse(pointer: &str) -> Result<Vec<String>, Error> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    let rest = match pointer.strip_prefix('/') {
+        Some(rest) => rest,
+        None => return Err(pointer_error(pointer, "does not start with '/'")),
+    };
+    rest.split('/')
+        .map(|token| unescape(token).ok_or_else(|| pointer_error(pointer, INVALID_ESCAPE)))
+        .collect()
+}
+
+const INVALID_ESCAPE: &str = "has a '~' that is not followed by 0 or 1";
+const INVALID_INDEX: &str = "has an array index that is out of range or not a number";
+const NOT_A_CONTAINER: &str = "points into a value that is neither an object nor an array";
+const MISSING_PARENT: &str = "points into a value that does not exist";
+
+fn unescape(token: &str) -> Option<String> {
+    let mut out = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => out.push('~'),
+                Some('1') => out.push('/'),
+                _ => return None,
+            },
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Escapes a key or index for use as a reference token, the reverse of `parse`.
+pub(crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// The array index a token stands for: digits without leading zeros. `-`, the position
+/// after the last element, is not an index.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    let digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    if !digits || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+pub(crate) fn pointer_error(pointer: &str, reason: &'static str) -> Error {
+    Error::InvalidPointer {
+        pointer: pointer.to_string(),
+        reason,
+    }
+}
+
+impl Value {
+    /// Looks up a value by its RFC 6901 JSON Pointer, e.g. `/glossary/GlossDiv/title` or
+    /// `/items/0`. In keys `~1` stands for `/` and `~0` for `~`. The empty pointer is the
+    /// value itself. `None` if the value is not there or the pointer is malformed.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        let tokens = parse(pointer).ok()?;
+        let mut target = self;
+        for token in &tokens {
+            target = match target {
+                Value::Object(obj) => obj.get(token)?,
+                Value::Array(arr) => arr.get(array_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Like `pointer`, with mutable access.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        let tokens = parse(pointer).ok()?;
+        self.walk_mut(&tokens)
+    }
+
+    /// Puts `value` where `pointer` points, replacing and returning what was there. Members
+    /// missing from an object are added, `-` as the last token appends to an array. The
+    /// parent has to exist already.
+    pub fn pointer_set(&mut self, pointer: &str, value: Value) -> Result<Option<Value>, Error> {
+        let mut tokens = parse(pointer)?;
+        let last = match tokens.pop() {
+            Some(last) => last,
+            None => return Ok(Some(mem::replace(self, value))),
+        };
+        match self.walk_mut(&tokens) {
+            Some(Value::Object(obj)) => Ok(obj.insert(last, value)),
+            Some(Value::Array(arr)) if last == "-" => {
+                arr.push(value);
+                Ok(None)
+            }
+            Some(Value::Array(arr)) => match array_index(&last) {
+                Some(index) if index < arr.len() => Ok(Some(mem::replace(&mut arr[index], value))),
+                _ => Err(pointer_error(pointer, INVALID_INDEX)),
+            },
+            Some(_) => Err(pointer_error(pointer, NOT_A_CONTAINER)),
+            None => Err(pointer_error(pointer, MISSING_PARENT)),
+        }
+    }
+
+    /// Removes and returns the value `pointer` points to. Array elements after it move
+    /// back, object members keep their order. `None` if there is nothing to remove.
+    pub fn pointer_remove(&mut self, pointer: &str) -> Option<Value> {
+        let mut tokens = parse(pointer).ok()?;
+        let last = tokens.pop()?;
+        match self.walk_mut(&tokens)? {
+            Value::Object(obj) => obj.shift_remove(&last),
+            Value::Array(arr) => {
+                let index = array_index(&last).filter(|index| *index < arr.len())?;
+                Some(arr.remove(index))
+            }
+            _ => None,
+        }
+    }
+
+    /// The value that the already unescaped `tokens` lead to.
+    pub(crate) fn walk_mut(&mut self, tokens: &[String]) -> Option<&mut Value> {
+        let mut target = self;
+        for token in tokens {
+            target = match target {
+                Value::Object(obj) => obj.get_mut(token)?,
+                Value::Array(arr) => arr.get_mut(array_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::json;
+
+    fn sample() -> Value {
+        json!({
+            "foo": ["bar", "baz"],
+            "": 0,
+            "a/b": 1,
+            "m~n": 8,
+            "nested": {"list": [{"id": 7}]}
+        })
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let value = sample();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/foo"), Some(&json!(["bar", "baz"])));
+        assert_eq!(value.pointer("/foo/0"), Some(&json!("bar")));
+        assert_eq!(value.pointer("/"), Some(&json!(0)));
+        assert_eq!(value.pointer("/a~1b"), Some(&json!(1)));
+        assert_eq!(value.pointer("/m~0n"), Some(&json!(8)));
+        assert_eq!(value.pointer("/nested/list/0/id"), Some(&json!(7)));
+    }
+
+    #[test]
+    fn test_missing_and_malformed() {
+        let value = sample();
+        for pointer in [
+            "foo", "/foo/2", "/foo/-", "/foo/01", "/foo/+1", "/m~2n", "/x/y", "/foo/0/z",
+        ] {
+            assert_eq!(value.pointer(pointer), None, "{}", pointer);
+        }
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let mut value = sample();
+        *value.pointer_mut("/nested/list/0/id").unwrap() = json!(8);
+        assert_eq!(value.pointer_set("/foo/-", json!("qux")), Ok(None));
+        assert_eq!(
+            value.pointer_set("/foo/0", json!("x")),
+            Ok(Some(json!("bar")))
+        );
+        assert_eq!(value.pointer_set("/nested/new", json!(true)), Ok(None));
+        assert_eq!(value.pointer_remove("/foo/1"), Some(json!("baz")));
+        assert_eq!(value.pointer_remove("/a~1b"), Some(json!(1)));
+        assert_eq!(value.pointer_remove("/a~1b"), None);
+        assert_eq!(
+            value.to_string(),
+            "{\"foo\":[\"x\",\"qux\"],\"\":0,\"m~n\":8,\"nested\":{\"list\":[{\"id\":8}],\"new\":true}}"
+        );
+
+        let whole = value.clone();
+        assert_eq!(value.pointer_set("", json!(null)), Ok(Some(whole)));
+        assert_eq!(value, Value::Null);
+    }
+
+    #[test]
+    fn test_set_errors() {
+        let mut value = sample();
+        assert_eq!(
+            value.pointer_set("/x/y", json!(1)).unwrap_err().to_string(),
+            "The JSON Pointer \"/x/y\" points into a value that does not exist"
+        );
+        assert_eq!(
+            value.pointer_set("/foo/5", json!(1)),
+            Err(pointer_error("/foo/5", INVALID_INDEX))
+        );
+        assert_eq!(
+            value.pointer_set("/m~0n/0", json!(1)),
+            Err(pointer_error("/m~0n/0", NOT_A_CONTAINER))
+        );
+        assert_eq!(
+            value.pointer_set("foo", json!(1)),
+            Err(pointer_error("foo", "does not start with '/'"))
+        );
+        assert_eq!(
+            value.pointer_set("/m~n", json!(1)),
+            Err(pointer_error("/m~n", INVALID_ESCAPE))
+        );
+    }
+}
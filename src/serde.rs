@@ -0,0 +1,193 @@
+//! serde support, enabled with the `serde` feature.
+//!
+//! `Value` and `Map` implement `Serialize` and `Deserialize`. `Deserializer` reads serde types
+//! straight from the tokenizer, so they get the same error messages and positions as `parse`,
+//! and `Serializer` writes them in the same compact format as `Value`'s `Display`.
+//!
+//! Numbers go through serde as `u64`, `i64` or `f64`, so with `arbitrary_precision` the
+//! exact text of numbers that do not fit them is lost.
+
+mod de;
+mod ser;
+mod value;
+
+pub use de::Deserializer;
+pub use ser::Serializer;
+
+use crate::error::Error;
+use std::fmt::Display;
+use std::io;
+
+/// Reads a `T` from JSON text.
+pub fn from_str<T: ::serde::de::DeserializeOwned>(input: &str) -> Result<T, Error> {
+    let mut deserializer = Deserializer::new(input);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Writes `value` as compact JSON text.
+pub fn to_string<T: ::serde::Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    let mut out = Vec::new();
+    to_writer(value, &mut out)?;
+    // The serializer only writes valid UTF-8
+    Ok(String::from_utf8(out).unwrap())
+}
+
+/// Writes `value` as compact JSON text into `writer`.
+pub fn to_writer<T, W>(value: &T, writer: W) -> Result<(), Error>
+where
+    T: ::serde::Serialize + ?Sized,
+    W: io::Write,
+{
+    value.serialize(&mut Serializer::new(writer))
+}
+
+impl ::serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom {
+            message: msg.to_string(),
+            position: None,
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField {
+            field,
+            path: String::new(),
+        }
+    }
+
+    fn unknown_variant(variant: &str, _expected: &'static [&'static str]) -> Self {
+        Error::UnknownVariant {
+            found: variant.to_string(),
+            path: String::new(),
+        }
+    }
+}
+
+impl ::serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Custom {
+            message: msg.to_string(),
+            position: None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::error::Position;
+    use crate::value::Value;
+    use ::serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Order {
+        order_id: u64,
+        note: Option<String>,
+        lines: Vec<Line>,
+        totals: BTreeMap<u32, f64>,
+        status: Status,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Line(String, u8);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Open,
+        Shipped { carrier: String },
+        Split(Vec<u64>),
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = "{\"orderId\":18446744073709551615,\"note\":null,\"lines\":[[\"a\\\"b\",2]],\
+                    \"totals\":{\"1\":2.5,\"30\":-0.0},\"status\":{\"Shipped\":{\"carrier\":\"UPS\"}}}";
+        let order: Order = from_str(text).unwrap();
+        assert_eq!(order.order_id, u64::MAX);
+        assert_eq!(order.lines, vec![Line("a\"b".to_string(), 2)]);
+        assert_eq!(
+            order.status,
+            Status::Shipped {
+                carrier: "UPS".to_string()
+            }
+        );
+        assert_eq!(to_string(&order).unwrap(), text);
+
+        assert_eq!(to_string(&Status::Open).unwrap(), "\"Open\"");
+        assert_eq!(
+            to_string(&Status::Split(vec![1])).unwrap(),
+            "{\"Split\":[1]}"
+        );
+        assert_eq!(from_str::<Status>(" \"Open\" "), Ok(Status::Open));
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(to_string(&0.1f32).unwrap(), "0.1");
+        assert_eq!(to_string(&1f32).unwrap(), "1.0");
+        assert_eq!(to_string(&1e-7f32).unwrap(), "1e-7");
+        assert_eq!(to_string(&f32::NAN).unwrap(), "null");
+        assert_eq!(to_string(&0.1f64).unwrap(), "0.1");
+        // The same as without serde
+        assert_eq!(crate::to_string(&0.1f32), "0.1");
+        assert_eq!(Value::from(0.1f32).to_string(), "0.1");
+    }
+
+    #[test]
+    fn test_value_matches_parse() {
+        let text = "{\"b\":[1,-2,3500.5,true,null],\"a\":{\"c\":\"\\u00e9\"}}";
+        let value: Value = from_str(text).unwrap();
+        assert_eq!(value, crate::parse(text).unwrap());
+        assert_eq!(to_string(&value).unwrap(), value.to_string());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = from_str::<Order>("{\"orderId\": \"7\"}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid type: string \"7\", expected u64 at line 1, column 13"
+        );
+
+        let err = from_str::<Vec<Order>>(
+            "[{\"orderId\": 1, \"lines\": [], \"totals\": {}, \"status\": \"Open\"},\n{\"orderId\": 2}]",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            Error::MissingField {
+                field: "lines",
+                path: "[1]".to_string(),
+            }
+        );
+
+        let err = from_str::<Vec<u8>>("[1, 2,]").unwrap_err();
+        assert_eq!(
+            err,
+            Error::UnexpectedToken {
+                expected: "a value",
+                found: "']'".to_string(),
+                position: Position {
+                    line: 1,
+                    column: 7,
+                    offset: 6
+                },
+            }
+        );
+        assert_eq!(
+            from_str::<u8>("1 2"),
+            Err(Error::TrailingCharacters {
+                position: Position {
+                    line: 1,
+                    column: 3,
+                    offset: 2
+                }
+            })
+        );
+    }
+}
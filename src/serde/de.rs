@@ -0,0 +1,402 @@
+use crate::error::{Error, Position};
+use crate::options::ParseOptions;
+use crate::tokenizer::token::{Span, Token};
+use crate::tokenizer::Tokenizer;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+/// Reads serde types from JSON text, token by token, without building a `Value` first.
+///
+/// Strings are always copied, so types that borrow `&str` from the input can not be read.
+pub struct Deserializer {
+    tokenizer: Tokenizer<'static>,
+    peeked: Option<(Token, Span)>,
+    depth: usize,
+    options: ParseOptions,
+}
+
+impl Deserializer {
+    pub fn new(input: &str) -> Self {
+        Self::with_options(input, ParseOptions::default())
+    }
+
+    pub fn with_options(input: &str, options: ParseOptions) -> Self {
+        Deserializer {
+            tokenizer: Tokenizer::with_options(input, options),
+            peeked: None,
+            depth: 0,
+            options,
+        }
+    }
+
+    /// Checks that nothing but whitespace follows the value that was read.
+    pub fn end(&mut self) -> Result<(), Error> {
+        match self.peek() {
+            Ok(None) => Ok(()),
+            Ok(Some((_, span))) => Err(Error::TrailingCharacters {
+                position: span.start,
+            }),
+            // Tokenizer errors always have a position
+            Err(e) => Err(Error::TrailingCharacters {
+                position: e.position().unwrap_or_default(),
+            }),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&(Token, Span)>, Error> {
+        if self.peeked.is_none() {
+            self.peeked = self.tokenizer.next_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<(Token, Span), Error> {
+        let next = match self.peeked.take() {
+            Some(next) => Some(next),
+            None => self.tokenizer.next_token()?,
+        };
+        next.ok_or(Error::UnexpectedEof {
+            expected,
+            position: self.tokenizer.position(),
+        })
+    }
+
+    /// Consumes the next token, which has to be `token`.
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), Error> {
+        let (next, span) = self.next(expected)?;
+        if next != token {
+            return Err(Error::UnexpectedToken {
+                expected,
+                found: next.to_string(),
+                position: span.start,
+            });
+        }
+        Ok(())
+    }
+
+    /// Goes one level deeper for the array or object starting at `position`.
+    fn enter(&mut self, position: Position) -> Result<(), Error> {
+        if self.depth >= self.options.max_depth {
+            return Err(Error::DepthLimitExceeded {
+                limit: self.options.max_depth,
+                position,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn peek_is(&mut self, token: &Token) -> Result<bool, Error> {
+        Ok(matches!(self.peek()?, Some((next, _)) if next == token))
+    }
+}
+
+/// Errors from serde, like "invalid type", get the position of the value they are about.
+fn locate(error: Error, position: Position) -> Error {
+    match error {
+        Error::Custom {
+            message,
+            position: None,
+        } => Error::Custom {
+            message,
+            position: Some(position),
+        },
+        error => error,
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let (token, span) = self.next("a value")?;
+        let result = match token {
+            Token::Null => visitor.visit_unit(),
+            Token::True => visitor.visit_bool(true),
+            Token::False => visitor.visit_bool(false),
+            Token::Number { value } => {
+                if let Some(num) = value.as_u64() {
+                    visitor.visit_u64(num)
+                } else if let Some(num) = value.as_i64() {
+                    visitor.visit_i64(num)
+                } else {
+                    visitor.visit_f64(value.as_f64())
+                }
+            }
+            Token::String { value } => visitor.visit_string(value),
+            Token::OpenSquareBrace => {
+                self.enter(span.start)?;
+                let value = visitor.visit_seq(SeqAccess {
+                    de: &mut *self,
+                    index: 0,
+                })?;
+                self.expect(Token::ClosedSquareBrace, "',' or ']'")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            Token::OpenCurlyBrace => {
+                self.enter(span.start)?;
+                let value = visitor.visit_map(MapAccess {
+                    de: &mut *self,
+                    key: None,
+                })?;
+                self.expect(Token::ClosedCurlyBrace, "',' or '}'")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            any => {
+                return Err(Error::UnexpectedToken {
+                    expected: "a value",
+                    found: any.to_string(),
+                    position: span.start,
+                })
+            }
+        };
+        result.map_err(|e| locate(e, span.start))
+    }
+
+    /// `null` is `None`, anything else is `Some`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.peek_is(&Token::Null)? {
+            self.next("a value")?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Enums are read like serde_json writes them: `"Variant"` for unit variants and
+    /// `{"Variant": content}` for the others.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let (token, span) = self.next("an enum variant")?;
+        let result = match token {
+            Token::String { value } => visitor.visit_enum(value.into_deserializer()),
+            Token::OpenCurlyBrace => {
+                self.enter(span.start)?;
+                let value = visitor.visit_enum(EnumAccess { de: &mut *self })?;
+                self.expect(Token::ClosedCurlyBrace, "'}'")?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            any => {
+                return Err(Error::UnexpectedToken {
+                    expected: "an enum variant",
+                    found: any.to_string(),
+                    position: span.start,
+                })
+            }
+        };
+        result.map_err(|e| locate(e, span.start))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct SeqAccess<'a> {
+    de: &'a mut Deserializer,
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.de.peek_is(&Token::ClosedSquareBrace)? {
+            return Ok(None);
+        }
+        if self.index > 0 {
+            self.de.expect(Token::Comma, "',' or ']'")?;
+        }
+        let value = seed
+            .deserialize(&mut *self.de)
+            .map_err(|e| e.with_index(self.index))?;
+        self.index += 1;
+        Ok(Some(value))
+    }
+}
+
+struct MapAccess<'a> {
+    de: &'a mut Deserializer,
+    /// The key of the member being read, for error paths
+    key: Option<String>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.de.peek_is(&Token::ClosedCurlyBrace)? {
+            return Ok(None);
+        }
+        if self.key.is_some() {
+            self.de.expect(Token::Comma, "',' or '}'")?;
+        }
+        let (token, span) = self.de.next("a key")?;
+        let key = match token {
+            Token::String { value } => value,
+            any => {
+                return Err(Error::UnexpectedToken {
+                    expected: "a key",
+                    found: any.to_string(),
+                    position: span.start,
+                })
+            }
+        };
+        self.de.expect(Token::Colon, "a colon (':')")?;
+        let value = seed
+            .deserialize(MapKey(key.clone()))
+            .map_err(|e| locate(e, span.start))?;
+        self.key = Some(key);
+        Ok(Some(value))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = seed.deserialize(&mut *self.de);
+        match &self.key {
+            Some(key) => value.map_err(|e| e.with_key(key)),
+            None => value,
+        }
+    }
+}
+
+struct EnumAccess<'a> {
+    de: &'a mut Deserializer,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumAccess<'a> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let (token, span) = self.de.next("an enum variant")?;
+        let variant = match token {
+            Token::String { value } => value,
+            any => {
+                return Err(Error::UnexpectedToken {
+                    expected: "an enum variant",
+                    found: any.to_string(),
+                    position: span.start,
+                })
+            }
+        };
+        self.de.expect(Token::Colon, "a colon (':')")?;
+        let value = seed
+            .deserialize(variant.into_deserializer())
+            .map_err(|e: Error| locate(e, span.start))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for EnumAccess<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+/// Object keys are strings in JSON, this also reads numbers and bools written in them,
+/// for maps like `HashMap<u32, T>`.
+struct MapKey(String);
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(parsed) => visitor.$visit(parsed),
+                    Err(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for MapKey {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
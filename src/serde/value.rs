@@ -0,0 +1,126 @@
+use crate::map::Map;
+use crate::number::Number;
+use crate::value::Value;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Object(obj) => obj.serialize(serializer),
+            Value::Array(arr) => serializer.collect_seq(arr),
+            Value::String(string) => serializer.serialize_str(string),
+            Value::Number(num) => num.serialize(serializer),
+            Value::Bool(boo) => serializer.serialize_bool(*boo),
+            Value::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl Serialize for Map {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Integers are passed on as `u64` or `i64`, everything else as `f64`.
+impl Serialize for Number {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(num) = self.as_u64() {
+            serializer.serialize_u64(num)
+        } else if let Some(num) = self.as_i64() {
+            serializer.serialize_i64(num)
+        } else {
+            serializer.serialize_f64(self.as_f64())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Map {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Map, D::Error> {
+        deserializer.deserialize_map(MapVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, boo: bool) -> Result<Value, E> {
+        Ok(Value::Bool(boo))
+    }
+
+    fn visit_i64<E: de::Error>(self, num: i64) -> Result<Value, E> {
+        Ok(Value::Number(num.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, num: u64) -> Result<Value, E> {
+        Ok(Value::Number(num.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, num: f64) -> Result<Value, E> {
+        Ok(Value::Number(num.into()))
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Value, E> {
+        Ok(Value::String(string.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, string: String) -> Result<Value, E> {
+        Ok(Value::String(string))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(elem) = seq.next_element()? {
+            arr.push(elem);
+        }
+        Ok(Value::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Value, A::Error> {
+        MapVisitor.visit_map(map).map(Value::Object)
+    }
+}
+
+struct MapVisitor;
+
+impl<'de> Visitor<'de> for MapVisitor {
+    type Value = Map;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Map, A::Error> {
+        let mut obj = Map::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            obj.insert(key, value);
+        }
+        Ok(obj)
+    }
+}
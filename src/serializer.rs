@@ -0,0 +1,322 @@
+pub mod pretty_config;
+
+use crate::map::Map;
+use crate::value::Value;
+use pretty_config::PrettyConfig;
+use std::fmt::{self, Write};
+
+/// Writes `value` as compact JSON text, without any whitespace between tokens.
+pub fn write_value<W: Write>(value: &Value, out: &mut W) -> fmt::Result {
+    match value {
+        Value::Object(obj) => {
+            out.write_char('{')?;
+            for (i, (key, val)) in obj.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_string(key, out)?;
+                out.write_char(':')?;
+                write_value(val, out)?;
+            }
+            out.write_char('}')
+        }
+        Value::Array(arr) => {
+            out.write_char('[')?;
+            for (i, val) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_value(val, out)?;
+            }
+            out.write_char(']')
+        }
+        Value::String(string) => write_string(string, out),
+        Value::Number(num) => write!(out, "{}", num),
+        Value::Bool(boo) => write!(out, "{}", boo),
+        Value::Null => out.write_str("null"),
+    }
+}
+
+pub fn write_string<W: Write>(string: &str, out: &mut W) -> fmt::Result {
+    out.write_char('"')?;
+    for c in string.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\u{0008}' => out.write_str("\\b")?,
+            '\u{000c}' => out.write_str("\\f")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{0000}'..='\u{001f}' => write!(out, "\\u{:04x}", c as u32)?,
+            _ => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Writes `value` laid out over multiple lines as described by `config`.
+pub fn write_pretty<W: Write>(
+    value: &Value,
+    config: &PrettyConfig,
+    level: usize,
+    out: &mut W,
+) -> fmt::Result {
+    match value {
+        Value::Object(obj) if !obj.is_empty() => {
+            if let Some(inline) = inline_form(value, config) {
+                return out.write_str(&inline);
+            }
+            out.write_char('{')?;
+            for (i, (key, val)) in entries(obj, config).into_iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_line_start(config, level + 1, out)?;
+                write_string(key, out)?;
+                write_colon(config, out)?;
+                write_pretty(val, config, level + 1, out)?;
+            }
+            write_line_start(config, level, out)?;
+            out.write_char('}')
+        }
+        Value::Array(arr) if !arr.is_empty() => {
+            if let Some(inline) = inline_form(value, config) {
+                return out.write_str(&inline);
+            }
+            out.write_char('[')?;
+            for (i, val) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.write_char(',')?;
+                }
+                write_line_start(config, level + 1, out)?;
+                write_pretty(val, config, level + 1, out)?;
+            }
+            write_line_start(config, level, out)?;
+            out.write_char(']')
+        }
+        _ => write_value(value, out),
+    }
+}
+
+/// The single line form of `value` if it fits in `config.max_inline_width`.
+fn inline_form(value: &Value, config: &PrettyConfig) -> Option<String> {
+    let mut out = LimitedWriter {
+        text: String::new(),
+        remaining: config.max_inline_width,
+    };
+    write_inline(value, config, &mut out).ok()?;
+    Some(out.text)
+}
+
+fn write_inline<W: Write>(value: &Value, config: &PrettyConfig, out: &mut W) -> fmt::Result {
+    match value {
+        Value::Object(obj) => {
+            out.write_char('{')?;
+            for (i, (key, val)) in entries(obj, config).into_iter().enumerate() {
+                if i > 0 {
+                    out.write_str(", ")?;
+                }
+                write_string(key, out)?;
+                write_colon(config, out)?;
+                write_inline(val, config, out)?;
+            }
+            out.write_char('}')
+        }
+        Value::Array(arr) => {
+            out.write_char('[')?;
+            for (i, val) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(", ")?;
+                }
+                write_inline(val, config, out)?;
+            }
+            out.write_char(']')
+        }
+        _ => write_value(value, out),
+    }
+}
+
+fn entries<'a>(obj: &'a Map, config: &PrettyConfig) -> Vec<(&'a String, &'a Value)> {
+    let mut entries: Vec<_> = obj.iter().collect();
+    if config.sort_keys {
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    entries
+}
+
+fn write_colon<W: Write>(config: &PrettyConfig, out: &mut W) -> fmt::Result {
+    if config.space_after_colon {
+        out.write_str(": ")
+    } else {
+        out.write_char(':')
+    }
+}
+
+fn write_line_start<W: Write>(config: &PrettyConfig, level: usize, out: &mut W) -> fmt::Result {
+    out.write_str(config.newline.as_str())?;
+    for _ in 0..level {
+        out.write_str(&config.indent)?;
+    }
+    Ok(())
+}
+
+/// Collects text until it gets longer than `remaining` characters, then fails,
+/// so finding out that a big value does not fit stays cheap.
+struct LimitedWriter {
+    text: String,
+    remaining: usize,
+}
+
+impl Write for LimitedWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let len = s.chars().count();
+        if len > self.remaining {
+            return Err(fmt::Error);
+        }
+        self.remaining -= len;
+        self.text.push_str(s);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::number::Number;
+
+    #[test]
+    fn test_compact_output() {
+        let mut obj = Map::new();
+        obj.insert(
+            "list".to_string(),
+            Value::Array(vec![
+                Value::Number(1.into()),
+                Value::Bool(false),
+                Value::Null,
+                Value::String("foo".to_string()),
+            ]),
+        );
+        assert_eq!(
+            Value::Object(obj).to_string(),
+            "{\"list\":[1,false,null,\"foo\"]}"
+        );
+        assert_eq!(Value::Object(Map::new()).to_string(), "{}");
+        assert_eq!(Value::Array(vec![]).to_string(), "[]");
+    }
+
+    #[test]
+    fn test_string_escaping() {
+        let value = Value::String("say \"hi\"\\\n\t\r\u{8}\u{c}\u{1}/é🦀".to_string());
+        assert_eq!(
+            value.to_string(),
+            "\"say \\\"hi\\\"\\\\\\n\\t\\r\\b\\f\\u0001/é🦀\""
+        );
+    }
+
+    #[test]
+    fn test_number_output() {
+        for (num, expected) in [
+            (Number::from(21), "21"),
+            (Number::from(-9007199254740993i64), "-9007199254740993"),
+            (Number::from(21.0), "21.0"),
+            (Number::from(-0.5), "-0.5"),
+            (Number::from(0.1), "0.1"),
+            (Number::from(1e21), "1e21"),
+            (Number::from(1.5e-7), "1.5e-7"),
+            (Number::from(123456789012.0), "123456789012.0"),
+            (Number::from(f64::NAN), "null"),
+            (Number::from(f64::INFINITY), "null"),
+        ] {
+            assert_eq!(Value::Number(num).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for num in [
+            0.1,
+            -1.0 / 3.0,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+            9007199254740993.0,
+            -0.0,
+        ] {
+            let text = Value::Number(num.into()).to_string();
+            let parsed = crate::parse(&text).unwrap().get_num().unwrap();
+            assert_eq!(parsed.to_bits(), num.to_bits(), "text: {}", text);
+        }
+        for num in [u64::MAX, 9007199254740993, 0] {
+            let text = Value::Number(num.into()).to_string();
+            assert_eq!(crate::parse(&text).unwrap(), Value::Number(num.into()));
+        }
+
+        let source = "{\"a\":[1.5,\"x\\ny\",{\"b\":null}],\"c\":true}";
+        let value = crate::parse(source).unwrap();
+        assert_eq!(crate::parse(&value.to_string()).unwrap(), value);
+    }
+
+    fn pretty(value: &Value, config: &PrettyConfig) -> String {
+        let mut out = String::new();
+        write_pretty(value, config, 0, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_pretty_default() {
+        let value = crate::parse("{\"a\":[1,{\"b\":null},[]],\"c\":{}}").unwrap();
+        let config = PrettyConfig {
+            sort_keys: true,
+            ..PrettyConfig::default()
+        };
+        assert_eq!(
+            pretty(&value, &config),
+            "{\n  \"a\": [\n    1,\n    {\n      \"b\": null\n    },\n    []\n  ],\n  \"c\": {}\n}"
+        );
+    }
+
+    #[test]
+    fn test_pretty_custom_layout() {
+        let value = crate::parse("{\"b\":true,\"a\":[1,2]}").unwrap();
+        let config = PrettyConfig {
+            indent: "\t".to_string(),
+            newline: pretty_config::Newline::CrLf,
+            space_after_colon: false,
+            sort_keys: true,
+            ..PrettyConfig::default()
+        };
+        assert_eq!(
+            pretty(&value, &config),
+            "{\r\n\t\"a\":[\r\n\t\t1,\r\n\t\t2\r\n\t],\r\n\t\"b\":true\r\n}"
+        );
+    }
+
+    #[test]
+    fn test_pretty_inline_width() {
+        let value = crate::parse(
+            "{\"short\":[1,2,3],\"nested\":{\"x\":[true]},\"long\":[\"abcdefgh\",\"ijklmnop\"]}",
+        )
+        .unwrap();
+        let config = PrettyConfig {
+            sort_keys: true,
+            max_inline_width: 16,
+            ..PrettyConfig::default()
+        };
+        assert_eq!(
+            pretty(&value, &config),
+            "{\n  \"long\": [\n    \"abcdefgh\",\n    \"ijklmnop\"\n  ],\n  \"nested\": {\"x\": [true]},\n  \"short\": [1, 2, 3]\n}"
+        );
+    }
+
+    #[test]
+    fn test_pretty_round_trip() {
+        let data = std::fs::read_to_string("tests/data.json").unwrap();
+        let value = crate::parse(&data).unwrap();
+        let config = PrettyConfig {
+            max_inline_width: 40,
+            ..PrettyConfig::default()
+        };
+        assert_eq!(crate::parse(&pretty(&value, &config)).unwrap(), value);
+    }
+}
@@ -0,0 +1,45 @@
+/// Line ending used by the pretty printer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Newline {
+    Lf,
+    CrLf,
+}
+
+impl Newline {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        }
+    }
+}
+
+/// How `to_string_pretty` lays out JSON text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrettyConfig {
+    /// Written once per nesting level, two spaces by default.
+    pub indent: String,
+    pub newline: Newline,
+    /// Write `"key": value` instead of `"key":value`, on by default.
+    pub space_after_colon: bool,
+    /// Write object members ordered by key instead of in map order.
+    pub sort_keys: bool,
+    /// Arrays and objects whose single line form is at most this many characters
+    /// are kept on one line, e.g. `[1, 2, 3]`. 0 always breaks them up.
+    pub max_inline_width: usize,
+    /// End the output with a newline.
+    pub trailing_newline: bool,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig {
+            indent: "  ".to_string(),
+            newline: Newline::Lf,
+            space_after_colon: true,
+            sort_keys: false,
+            max_inline_width: 0,
+            trailing_newline: false,
+        }
+    }
+}
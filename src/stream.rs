@@ -0,0 +1,508 @@
+//! Reading JSON as a stream of events, without building a `Value`.
+//!
+//! Read from a file or socket with `Reader::from_reader`, memory use only depends on the
+//! nesting depth and the longest string, so it suits documents too large to hold as a
+//! `Value`:
+//!
+//! ```text
+//! let mut reader = jsonlib::stream::Reader::from_reader(File::open("export.json")?);
+//! while let Some(event) = reader.next_event()? {
+//!     match event {
+//!         Event::Key("id") => {
+//!             if let Some(Event::Number(id)) = reader.next_event()? {
+//!                 ids.push(id);
+//!             }
+//!         }
+//!         // Only arrays and objects need skipping, other values are a single event
+//!         Event::Key("history") => {
+//!             if let Some(Event::StartObject | Event::StartArray) = reader.next_event()? {
+//!                 reader.skip()?;
+//!             }
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use crate::error::{Error, Position};
+use crate::map::Map;
+use crate::number::Number;
+use crate::options::ParseOptions;
+use crate::tokenizer::token::{Span, Token};
+use crate::tokenizer::Tokenizer;
+use crate::value::Value;
+use std::io::Read;
+use std::mem;
+
+/// What the reader found next. Strings and keys borrow from the reader and are only
+/// valid until the next call to `Reader::next_event`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    /// The key of the member whose value comes next
+    Key(&'a str),
+    EndObject,
+    StartArray,
+    EndArray,
+    String(&'a str),
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+/// What the next token has to be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Value,
+    /// Right after '[', a value or ']'
+    FirstElement,
+    /// Right after '{', a key or '}'
+    FirstKey,
+    Key,
+    /// ',' or the end of the innermost array or object, or the end of the input
+    AfterValue,
+    Done,
+}
+
+/// An event with its string kept in the reader.
+enum Step {
+    Event(Event<'static>),
+    Key,
+    String,
+}
+
+/// A pull parser: every call to `next_event` reads just enough of the input for one event.
+///
+/// The input is checked as it is read, so an error can come after events for the valid
+/// part before it.
+pub struct Reader<'a> {
+    tokenizer: Tokenizer<'a>,
+    state: State,
+    /// For each open array or object, whether it is an object
+    open: Vec<bool>,
+    /// The last key or string read
+    text: String,
+    options: ParseOptions,
+}
+
+impl Reader<'static> {
+    pub fn new(input: &str) -> Self {
+        Self::with_options(input, ParseOptions::default())
+    }
+
+    pub fn with_options(input: &str, options: ParseOptions) -> Self {
+        Self::from_tokenizer(Tokenizer::with_options(input, options), options)
+    }
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the input from `reader` as the events are read, see `Tokenizer::from_reader`.
+    pub fn from_reader(reader: impl Read + 'a) -> Self {
+        Self::from_reader_with_options(reader, ParseOptions::default())
+    }
+
+    pub fn from_reader_with_options(reader: impl Read + 'a, options: ParseOptions) -> Self {
+        Self::from_tokenizer(Tokenizer::from_reader(reader, options), options)
+    }
+
+    fn from_tokenizer(tokenizer: Tokenizer<'a>, options: ParseOptions) -> Self {
+        Reader {
+            tokenizer,
+            state: State::Value,
+            open: vec![],
+            text: String::new(),
+            options,
+        }
+    }
+
+    /// Reads the next event, `None` once the whole value has been read.
+    pub fn next_event(&mut self) -> Result<Option<Event<'_>>, Error> {
+        let step = match self.step()? {
+            Some(step) => step,
+            None => return Ok(None),
+        };
+        Ok(Some(match step {
+            Step::Event(event) => event,
+            Step::Key => Event::Key(&self.text),
+            Step::String => Event::String(&self.text),
+        }))
+    }
+
+    /// Skips what is left of the innermost open array or object, including its end.
+    /// Right after `StartObject` or `StartArray` that is the whole value. The skipped
+    /// part is still checked, but no events are made for it.
+    pub fn skip(&mut self) -> Result<(), Error> {
+        let depth = self.open.len();
+        while self.open.len() >= depth && depth > 0 {
+            if self.step()?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the value that the next event would start as a whole, e.g. after a `Key` or
+    /// for each record of an array. `None` if the innermost array ends instead, or after
+    /// the whole input was read.
+    pub fn read_value(&mut self) -> Result<Option<Value>, Error> {
+        if self.open.last() == Some(&true) && self.state != State::Value {
+            return Err(Error::Custom {
+                message: "In an object read_value has to come after a key".to_string(),
+                position: Some(self.position()),
+            });
+        }
+        match self.step()? {
+            Some(Step::Event(Event::EndArray)) | None => Ok(None),
+            Some(step) => self.build(step).map(Some),
+        }
+    }
+
+    /// The value that starts with `step`.
+    fn build(&mut self, step: Step) -> Result<Value, Error> {
+        Ok(match step {
+            Step::String => Value::String(mem::take(&mut self.text)),
+            Step::Event(Event::Number(number)) => Value::Number(number),
+            Step::Event(Event::Bool(b)) => Value::Bool(b),
+            Step::Event(Event::Null) => Value::Null,
+            Step::Event(Event::StartArray) => {
+                let mut arr = vec![];
+                loop {
+                    match self.next_step()? {
+                        Step::Event(Event::EndArray) => break Value::Array(arr),
+                        step => arr.push(self.build(step)?),
+                    }
+                }
+            }
+            Step::Event(Event::StartObject) => {
+                let mut obj = Map::new();
+                loop {
+                    match self.next_step()? {
+                        Step::Key => {
+                            let key = mem::take(&mut self.text);
+                            let step = self.next_step()?;
+                            obj.insert(key, self.build(step)?);
+                        }
+                        _ => break Value::Object(obj),
+                    }
+                }
+            }
+            // Only values are built, keys and ends are read by the loops above
+            _ => unreachable!(),
+        })
+    }
+
+    /// A step inside an array or object, where the input can not end without an error.
+    fn next_step(&mut self) -> Result<Step, Error> {
+        Ok(self.step()?.unwrap())
+    }
+
+    /// How deep the reader is in arrays and objects, 0 outside of them.
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Position of the next character to read.
+    pub fn position(&self) -> Position {
+        self.tokenizer.position()
+    }
+
+    /// Like `read_step`, but nothing more is read after an error.
+    fn step(&mut self) -> Result<Option<Step>, Error> {
+        self.read_step().inspect_err(|_| self.state = State::Done)
+    }
+
+    fn read_step(&mut self) -> Result<Option<Step>, Error> {
+        loop {
+            match self.state {
+                State::Done => return Ok(None),
+                State::Value | State::FirstElement => {
+                    let (token, span) = self.next_token("a value")?;
+                    if token == Token::ClosedSquareBrace && self.state == State::FirstElement {
+                        return Ok(Some(self.close()));
+                    }
+                    return self.value(token, span).map(Some);
+                }
+                State::Key | State::FirstKey => {
+                    let (token, span) = self.next_token("a key")?;
+                    match token {
+                        Token::String { value } => {
+                            self.text = value;
+                            self.expect_colon()?;
+                            self.state = State::Value;
+                            return Ok(Some(Step::Key));
+                        }
+                        Token::ClosedCurlyBrace if self.state == State::FirstKey => {
+                            return Ok(Some(self.close()));
+                        }
+                        any => return Err(unexpected(any, span, "a key")),
+                    }
+                }
+                State::AfterValue => {
+                    let in_object = match self.open.last() {
+                        Some(in_object) => *in_object,
+                        None => return self.end().map(|_| None),
+                    };
+                    let expected = if in_object {
+                        "',' or '}'"
+                    } else {
+                        "',' or ']'"
+                    };
+                    let (token, span) = self.next_token(expected)?;
+                    match token {
+                        Token::Comma if in_object => self.state = State::Key,
+                        Token::Comma => self.state = State::Value,
+                        Token::ClosedCurlyBrace if in_object => return Ok(Some(self.close())),
+                        Token::ClosedSquareBrace if !in_object => return Ok(Some(self.close())),
+                        any => return Err(unexpected(any, span, expected)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, token: Token, span: Span) -> Result<Step, Error> {
+        self.state = State::AfterValue;
+        let event = match token {
+            Token::String { value } => {
+                self.text = value;
+                return Ok(Step::String);
+            }
+            Token::Number { value } => Event::Number(value),
+            Token::True => Event::Bool(true),
+            Token::False => Event::Bool(false),
+            Token::Null => Event::Null,
+            Token::OpenCurlyBrace => {
+                self.enter(true, span.start)?;
+                self.state = State::FirstKey;
+                Event::StartObject
+            }
+            Token::OpenSquareBrace => {
+                self.enter(false, span.start)?;
+                self.state = State::FirstElement;
+                Event::StartArray
+            }
+            any => return Err(unexpected(any, span, "a value")),
+        };
+        Ok(Step::Event(event))
+    }
+
+    /// Goes one level deeper for the array or object starting at `position`.
+    fn enter(&mut self, object: bool, position: Position) -> Result<(), Error> {
+        if self.open.len() >= self.options.max_depth {
+            return Err(Error::DepthLimitExceeded {
+                limit: self.options.max_depth,
+                position,
+            });
+        }
+        self.open.push(object);
+        Ok(())
+    }
+
+    /// Ends the innermost array or object, after its closing brace was read.
+    fn close(&mut self) -> Step {
+        self.state = State::AfterValue;
+        match self.open.pop() {
+            Some(true) => Step::Event(Event::EndObject),
+            _ => Step::Event(Event::EndArray),
+        }
+    }
+
+    fn expect_colon(&mut self) -> Result<(), Error> {
+        match self.next_token("a colon (':')")? {
+            (Token::Colon, _) => Ok(()),
+            (any, span) => Err(unexpected(any, span, "a colon (':')")),
+        }
+    }
+
+    /// Checks that nothing but whitespace follows the value.
+    fn end(&mut self) -> Result<(), Error> {
+        self.state = State::Done;
+        match self.tokenizer.next_token() {
+            Ok(None) => Ok(()),
+            Ok(Some((_, span))) => Err(Error::TrailingCharacters {
+                position: span.start,
+            }),
+            // The input itself could not be read, there is nothing wrong with its content
+            Err(e @ (Error::Io { .. } | Error::InvalidUtf8 { .. })) => Err(e),
+            // Tokenizer errors always have a position
+            Err(e) => Err(Error::TrailingCharacters {
+                position: e.position().unwrap_or_default(),
+            }),
+        }
+    }
+
+    fn next_token(&mut self, expected: &'static str) -> Result<(Token, Span), Error> {
+        self.tokenizer
+            .next_token()?
+            .ok_or_else(|| Error::UnexpectedEof {
+                expected,
+                position: self.tokenizer.position(),
+            })
+    }
+}
+
+fn unexpected(token: Token, span: Span, expected: &'static str) -> Error {
+    Error::UnexpectedToken {
+        expected,
+        found: token.to_string(),
+        position: span.start,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    fn events(input: &str) -> Result<Vec<String>, Error> {
+        let mut reader = Reader::new(input);
+        let mut events = vec![];
+        while let Some(event) = reader.next_event()? {
+            events.push(match event {
+                Event::Number(number) => format!("Number({})", number),
+                event => format!("{:?}", event),
+            });
+        }
+        Ok(events)
+    }
+
+    #[test]
+    fn test_events() {
+        assert_eq!(
+            events(r#" {"a": [1, true, null], "b": {}, "c": "x", "d": []} "#).unwrap(),
+            [
+                "StartObject",
+                "Key(\"a\")",
+                "StartArray",
+                "Number(1)",
+                "Bool(true)",
+                "Null",
+                "EndArray",
+                "Key(\"b\")",
+                "StartObject",
+                "EndObject",
+                "Key(\"c\")",
+                "String(\"x\")",
+                "Key(\"d\")",
+                "StartArray",
+                "EndArray",
+                "EndObject"
+            ]
+        );
+        assert_eq!(events("\"top\"").unwrap(), ["String(\"top\")"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        for (input, message) in [
+            ("", "Expected a value, got end of input at line 1, column 1"),
+            ("[1,]", "Expected a value, got ']' at line 1, column 4"),
+            (
+                "[1 2]",
+                "Expected ',' or ']', got the number 2 at line 1, column 4",
+            ),
+            (
+                "{\"a\" 1}",
+                "Expected a colon (':'), got the number 1 at line 1, column 6",
+            ),
+            ("{\"a\":1,}", "Expected a key, got '}' at line 1, column 8"),
+            (
+                "{\"a\":1]",
+                "Expected ',' or '}', got ']' at line 1, column 7",
+            ),
+            (
+                "[] []",
+                "Unexpected content after the value at line 1, column 4",
+            ),
+        ] {
+            assert_eq!(events(input).unwrap_err().to_string(), message, "{}", input);
+        }
+
+        // Events for the valid part come first, and nothing after the error
+        let mut reader = Reader::new("[1, x]");
+        assert_eq!(reader.next_event().unwrap(), Some(Event::StartArray));
+        assert_eq!(
+            reader.next_event().unwrap(),
+            Some(Event::Number(Number::from(1)))
+        );
+        assert!(reader.next_event().is_err());
+        assert_eq!(reader.next_event().unwrap(), None);
+
+        let options = ParseOptions {
+            max_depth: 2,
+            ..ParseOptions::default()
+        };
+        let mut reader = Reader::with_options("[[[]]]", options);
+        reader.next_event().unwrap();
+        reader.next_event().unwrap();
+        assert!(matches!(
+            reader.next_event(),
+            Err(Error::DepthLimitExceeded { limit: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut reader = Reader::new(r#"[{"id": 1, "big": {"x": [[1], {"y": 2}]}, "n": "a"}, 2]"#);
+        assert_eq!(reader.next_event().unwrap(), Some(Event::StartArray));
+        assert_eq!(reader.next_event().unwrap(), Some(Event::StartObject));
+        assert_eq!(reader.next_event().unwrap(), Some(Event::Key("id")));
+        assert_eq!(
+            reader.next_event().unwrap(),
+            Some(Event::Number(Number::from(1)))
+        );
+        assert_eq!(reader.next_event().unwrap(), Some(Event::Key("big")));
+        assert_eq!(reader.next_event().unwrap(), Some(Event::StartObject));
+        reader.skip().unwrap();
+        assert_eq!(reader.depth(), 2);
+        assert_eq!(reader.next_event().unwrap(), Some(Event::Key("n")));
+        // Skips the rest of the record
+        reader.skip().unwrap();
+        assert_eq!(reader.depth(), 1);
+        assert_eq!(
+            reader.next_event().unwrap(),
+            Some(Event::Number(Number::from(2)))
+        );
+        assert_eq!(reader.next_event().unwrap(), Some(Event::EndArray));
+        assert_eq!(reader.next_event().unwrap(), None);
+
+        let mut reader = Reader::new("[1, [2, 3]");
+        reader.next_event().unwrap();
+        assert!(reader.skip().is_err());
+        assert_eq!(reader.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_value() {
+        let input = r#"{"count": 2, "records": [{"id": 1, "tags": ["a"]}, [], "x"], "end": null}"#;
+        let mut reader = Reader::from_reader(input.as_bytes());
+        assert_eq!(reader.next_event().unwrap(), Some(Event::StartObject));
+        assert!(matches!(reader.read_value(), Err(Error::Custom { .. })));
+
+        let mut reader = Reader::from_reader(input.as_bytes());
+        reader.next_event().unwrap();
+        assert_eq!(reader.next_event().unwrap(), Some(Event::Key("count")));
+        assert_eq!(reader.read_value().unwrap(), Some(Value::from(2)));
+        assert_eq!(reader.next_event().unwrap(), Some(Event::Key("records")));
+        assert_eq!(reader.next_event().unwrap(), Some(Event::StartArray));
+        let mut records = vec![];
+        while let Some(record) = reader.read_value().unwrap() {
+            records.push(record);
+        }
+        assert_eq!(
+            Value::Array(records),
+            crate::json!([{"id": 1, "tags": ["a"]}, [], "x"])
+        );
+        assert_eq!(reader.next_event().unwrap(), Some(Event::Key("end")));
+        assert_eq!(reader.read_value().unwrap(), Some(Value::Null));
+        assert_eq!(reader.next_event().unwrap(), Some(Event::EndObject));
+        assert_eq!(reader.read_value().unwrap(), None);
+
+        let mut reader = Reader::new("[1, {\"a\": [}]");
+        reader.next_event().unwrap();
+        reader.read_value().unwrap();
+        assert_eq!(
+            reader.read_value().unwrap_err().to_string(),
+            "Expected a value, got '}' at line 1, column 12"
+        );
+    }
+}
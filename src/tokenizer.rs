@@ -1,247 +1,363 @@
-pub mod input_stream;
+mod input_stream;
 pub mod token;
 
-use input_stream::InputStream;
-use token::Token;
+use crate::error::{Error, Position};
+use crate::number::Number;
+use crate::options::ParseOptions;
+use input_stream::{Failure, InputStream};
+use std::io::Read;
+use token::{Span, Token};
 
-pub struct Tokenizer {
-    stream: InputStream,
+/// Splits JSON text into tokens, each with the span of the input it came from.
+pub struct Tokenizer<'a> {
+    stream: InputStream<'a>,
+    options: ParseOptions,
     line_number: usize,
     char_number: usize,
 }
 
-impl Tokenizer {
+impl Tokenizer<'static> {
     pub fn new(input: &str) -> Self {
+        Self::with_options(input, ParseOptions::default())
+    }
+
+    pub fn with_options(input: &str, options: ParseOptions) -> Self {
+        Self::from_stream(InputStream::new(input), options)
+    }
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Reads the text from `reader` as it goes, a chunk at a time, so the input never
+    /// has to be in memory as a whole. It has to be UTF-8, read errors and invalid
+    /// UTF-8 are reported at the byte offset they happened at.
+    pub fn from_reader(reader: impl Read + 'a, options: ParseOptions) -> Self {
+        Self::from_stream(InputStream::from_reader(reader), options)
+    }
+
+    fn from_stream(stream: InputStream<'a>, options: ParseOptions) -> Self {
         Tokenizer {
-            stream: InputStream::new(input),
+            stream,
+            options,
             line_number: 1,
             char_number: 0,
         }
     }
 
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    /// Reads all the tokens without their spans.
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, Error> {
         let mut res = vec![];
-        loop {
-            let c: char;
-            match self.stream.get_char() {
-                Some(character) => {
-                    c = character;
-                    self.char_number += 1;
-                }
-                None => break,
-            }
+        while let Some((token, _)) = self.next_token()? {
+            res.push(token);
+        }
+        Ok(res)
+    }
 
-            match c {
-                '"' => self.handle_string(&mut res),
-                ',' => res.push(Token::Comma),
-                '{' => res.push(Token::OpenCurlyBrace),
-                '}' => res.push(Token::ClosedCurlyBrace),
-                '[' => res.push(Token::OpenSquareBrace),
-                ']' => res.push(Token::ClosedSquareBrace),
-                ':' => res.push(Token::Colon),
-                't' => self.handle_true(&mut res),
-                'f' => self.handle_false(&mut res),
-                '0'..='9' => {
-                    self.stream.unseek();
-                    self.handle_number(&mut res);
-                }
-                '\n' => {
-                    self.char_number = 0;
-                    self.line_number += 1;
-                }
-                //all whitespace
-                '\u{0009}' | '\u{000b}' | '\u{000c}' | '\u{000d}' | '\u{0020}' | '\u{0085}'
-                | '\u{00a0}' | '\u{1680}' | '\u{180e}' | '\u{2000}' | '\u{2001}' | '\u{2002}'
-                | '\u{2003}' | '\u{2004}' | '\u{2005}' | '\u{2006}' | '\u{2007}' | '\u{2008}'
-                | '\u{2009}' | '\u{200a}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2028}'
-                | '\u{2029}' | '\u{202f}' | '\u{205f}' | '\u{2060}' | '\u{3000}' | '\u{feff}' => {}
-
-                _ => res.push(Token::BadToken {
-                    line_number: self.line_number,
-                    char_number: self.char_number,
-                }),
-            }
+    /// Reads the next token and the span it covers, `None` once the input is exhausted.
+    pub fn next_token(&mut self) -> Result<Option<(Token, Span)>, Error> {
+        let token = self.read_token();
+        // The stream looks like it ended where it failed, so the failure is the real error
+        match self.stream.take_failure() {
+            Some(Failure::Io(e)) => Err(Error::Io {
+                kind: e.kind(),
+                message: e.to_string(),
+                position: self.position(),
+            }),
+            Some(Failure::InvalidUtf8) => Err(Error::InvalidUtf8 {
+                position: self.position(),
+            }),
+            None => token,
         }
-        res
     }
-    fn handle_number(&mut self, tokens: &mut Vec<Token>) {
-        let mut number = String::new();
-        let mut dot = false;
+
+    fn read_token(&mut self) -> Result<Option<(Token, Span)>, Error> {
         loop {
-            let c: char;
-            match self.stream.get_char() {
-                Some(character) => c = character,
-                None => break,
-            }
-            match c {
-                '0'..='9' => number.push(c),
-                '.' if !dot => {
-                    number.push(c);
-                    dot = true;
-                }
-                ' ' | ',' | '}' | ']' => {
-                    self.stream.unseek();
-                    break;
-                }
+            let start = self.position();
+            let c = match self.next_char() {
+                Some(character) => character,
+                None => return Ok(None),
+            };
+
+            let token = match c {
+                '"' => self.handle_string(start)?,
+                ',' => Token::Comma,
+                '{' => Token::OpenCurlyBrace,
+                '}' => Token::ClosedCurlyBrace,
+                '[' => Token::OpenSquareBrace,
+                ']' => Token::ClosedSquareBrace,
+                ':' => Token::Colon,
+                't' => self.handle_literal("rue", Token::True)?,
+                'f' => self.handle_literal("alse", Token::False)?,
+                'n' => self.handle_literal("ull", Token::Null)?,
+                '-' | '0'..='9' => self.handle_number(c, start)?,
+                // RFC 8259 only allows these four whitespace characters
+                ' ' | '\t' | '\n' | '\r' => continue,
+
                 _ => {
-                    tokens.push(Token::BadToken {
-                        line_number: self.line_number,
-                        char_number: self.char_number,
-                    });
-                    return;
+                    return Err(Error::UnexpectedCharacter {
+                        found: c,
+                        position: start,
+                    })
                 }
-            }
+            };
+            let span = Span {
+                start,
+                end: self.position(),
+            };
+            return Ok(Some((token, span)));
         }
-        let number: f64 = number.parse().unwrap();
-        tokens.push(Token::Number { value: number });
     }
 
-    fn handle_true(&mut self, tokens: &mut Vec<Token>) {
-        //TODO: fix the unwraping
-        let mut next: char;
-        next = if let Some(character) = self.stream.get_char() {
-            character
-        } else {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
-        };
-        if next != 'r' {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
+    /// Position of the next character in the stream.
+    pub fn position(&self) -> Position {
+        Position {
+            line: self.line_number,
+            column: self.char_number + 1,
+            offset: self.stream.offset(),
         }
-        next = if let Some(character) = self.stream.get_char() {
-            character
-        } else {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
-        };
-        if next != 'u' {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
+    }
+
+    fn handle_number(&mut self, first: char, start: Position) -> Result<Token, Error> {
+        let number = self.scan_number(first)?;
+        let value = Number::from_literal(&number);
+        // JSON has no infinity, so a number too large for an f64 could only be written as
+        // null. Exact literals are kept as they are.
+        if !cfg!(feature = "arbitrary_precision") && value.as_f64().is_infinite() {
+            return Err(Error::InvalidNumber { position: start });
         }
-        next = if let Some(character) = self.stream.get_char() {
-            character
+        Ok(Token::Number { value })
+    }
+
+    /// Scans a number following the RFC 8259 grammar: `[ minus ] int [ frac ] [ exp ]`.
+    /// Fails at the position of the first character violating it.
+    fn scan_number(&mut self, first: char) -> Result<String, Error> {
+        let mut number = String::from(first);
+        let first_digit = if first == '-' {
+            let position = self.position();
+            match self.next_char() {
+                Some(c @ '0'..='9') => {
+                    number.push(c);
+                    c
+                }
+                _ => return Err(Error::InvalidNumber { position }),
+            }
         } else {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
+            first
         };
-        if next != 'e' {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
+        if first_digit != '0' {
+            self.scan_digits(&mut number);
+        }
+        if self.peek_char() == Some('.') {
+            self.next_char();
+            number.push('.');
+            self.expect_digits(&mut number)?;
+        }
+        if let Some(c @ ('e' | 'E')) = self.peek_char() {
+            self.next_char();
+            number.push(c);
+            if let Some(sign @ ('+' | '-')) = self.peek_char() {
+                self.next_char();
+                number.push(sign);
+            }
+            self.expect_digits(&mut number)?;
+        }
+        if !self.at_boundary() {
+            return Err(Error::InvalidNumber {
+                position: self.position(),
             });
-            return;
         }
-
-        tokens.push(Token::True);
+        Ok(number)
     }
 
-    fn handle_false(&mut self, tokens: &mut Vec<Token>) {
-        //TODO: fix the unwraping
-        let mut next: char;
-        next = if let Some(character) = self.stream.get_char() {
-            character
-        } else {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
-        };
-        if next != 'a' {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
+    /// Like `scan_digits`, but at least one digit has to be there.
+    fn expect_digits(&mut self, number: &mut String) -> Result<(), Error> {
+        let position = self.position();
+        if self.scan_digits(number) == 0 {
+            return Err(Error::InvalidNumber { position });
         }
-        next = if let Some(character) = self.stream.get_char() {
-            character
-        } else {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
-        };
-        if next != 'l' {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
+        Ok(())
+    }
+
+    /// Pushes consecutive digits into `number` and returns how many there were.
+    fn scan_digits(&mut self, number: &mut String) -> usize {
+        let mut count = 0;
+        while let Some(c @ '0'..='9') = self.peek_char() {
+            self.next_char();
+            number.push(c);
+            count += 1;
         }
-        next = if let Some(character) = self.stream.get_char() {
-            character
-        } else {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
-        };
-        if next != 's' {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
+        count
+    }
+
+    fn handle_literal(&mut self, rest: &str, token: Token) -> Result<Token, Error> {
+        for expected in rest.chars() {
+            let position = self.position();
+            match self.next_char() {
+                Some(c) if c == expected => {}
+                Some(c) => return Err(Error::UnexpectedCharacter { found: c, position }),
+                None => {
+                    return Err(Error::UnexpectedEof {
+                        expected: "a literal",
+                        position,
+                    })
+                }
+            }
         }
-        next = if let Some(character) = self.stream.get_char() {
-            character
-        } else {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
+        if !self.at_boundary() {
+            let position = self.position();
+            return Err(Error::UnexpectedCharacter {
+                // Not a boundary, so there is a character
+                found: self.peek_char().unwrap(),
+                position,
             });
-            return;
-        };
-        if next != 'e' {
-            tokens.push(Token::BadToken {
-                line_number: self.line_number,
-                char_number: self.char_number,
-            });
-            return;
         }
 
-        tokens.push(Token::False);
+        Ok(token)
     }
 
-    fn handle_string(&mut self, tokens: &mut Vec<Token>) {
+    /// Checks that the next character can end a literal (`truex` or `nullx` are not literals).
+    fn at_boundary(&mut self) -> bool {
+        match self.peek_char() {
+            Some(c) => matches!(c, ',' | ':' | '}' | ']' | ' ' | '\t' | '\n' | '\r'),
+            None => true,
+        }
+    }
+
+    fn handle_string(&mut self, start: Position) -> Result<Token, Error> {
         let mut string_val = String::new();
         loop {
-            let c: char;
-            match self.stream.get_char() {
-                Some(character) => c = character,
-                None => {
-                    tokens.push(Token::BadToken {
-                        line_number: self.line_number,
-                        char_number: self.char_number,
-                    });
-                    return;
-                }
-            }
+            let position = self.position();
+            let c = match self.next_char() {
+                Some(character) => character,
+                None => return Err(Error::UnterminatedString { position: start }),
+            };
             match c {
                 '"' => break,
+                '\\' => self.handle_escape(&mut string_val, position)?,
+                '\u{0000}'..='\u{001f}' => return Err(Error::ControlCharacter { position }),
                 _ => string_val.push(c),
             }
         }
-        tokens.push(Token::String { value: string_val });
+        Ok(Token::String { value: string_val })
+    }
+
+    /// Decodes the escape sequence following the backslash at `position`.
+    fn handle_escape(&mut self, string_val: &mut String, position: Position) -> Result<(), Error> {
+        let decoded = match self.next_char() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{0008}',
+            Some('f') => '\u{000c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let unit = self.read_hex4(position)?;
+                return self.push_code_unit(unit, string_val, position);
+            }
+            Some(_) => return Err(Error::InvalidEscape { position }),
+            // Leave the missing closing quote for `handle_string` to report
+            None => return Ok(()),
+        };
+        string_val.push(decoded);
+        Ok(())
+    }
+
+    /// Pushes a UTF-16 code unit from the `\uXXXX` escape at `position`, pairing high
+    /// surrogates with a directly following `\uXXXX` low surrogate.
+    fn push_code_unit(
+        &mut self,
+        unit: u16,
+        string_val: &mut String,
+        position: Position,
+    ) -> Result<(), Error> {
+        match unit {
+            0xd800..=0xdbff => {
+                let next_position = self.position();
+                if !self.next_is_unicode_escape() {
+                    return self.push_lone_surrogate(string_val, position);
+                }
+                match self.read_hex4(next_position)? {
+                    low @ 0xdc00..=0xdfff => {
+                        let code = 0x10000
+                            + ((u32::from(unit) - 0xd800) << 10)
+                            + (u32::from(low) - 0xdc00);
+                        // Every surrogate pair maps to a valid scalar value
+                        string_val.push(char::from_u32(code).unwrap());
+                        Ok(())
+                    }
+                    other => {
+                        self.push_lone_surrogate(string_val, position)?;
+                        self.push_code_unit(other, string_val, next_position)
+                    }
+                }
+            }
+            0xdc00..=0xdfff => self.push_lone_surrogate(string_val, position),
+            // Anything outside the surrogate range is a valid scalar value
+            _ => {
+                string_val.push(char::from_u32(u32::from(unit)).unwrap());
+                Ok(())
+            }
+        }
+    }
+
+    fn push_lone_surrogate(
+        &mut self,
+        string_val: &mut String,
+        position: Position,
+    ) -> Result<(), Error> {
+        if !self.options.replace_lone_surrogates {
+            return Err(Error::InvalidEscape { position });
+        }
+        string_val.push(char::REPLACEMENT_CHARACTER);
+        Ok(())
+    }
+
+    /// Consumes a `\u` if it comes next in the stream.
+    fn next_is_unicode_escape(&mut self) -> bool {
+        if self.peek_char() != Some('\\') {
+            return false;
+        }
+        self.stream.get_char();
+        if self.peek_char() != Some('u') {
+            self.stream.unseek();
+            return false;
+        }
+        self.stream.get_char();
+        self.char_number += 2;
+        true
+    }
+
+    /// Reads the four hex digits of the `\uXXXX` escape at `position`.
+    fn read_hex4(&mut self, position: Position) -> Result<u16, Error> {
+        let mut unit = 0;
+        for _ in 0..4 {
+            match self.peek_char().and_then(|c| c.to_digit(16)) {
+                Some(digit) => {
+                    self.next_char();
+                    unit = unit * 16 + digit as u16;
+                }
+                None => return Err(Error::InvalidEscape { position }),
+            }
+        }
+        Ok(unit)
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.stream.get_char()?;
+        if c == '\n' {
+            self.line_number += 1;
+            self.char_number = 0;
+        } else {
+            self.char_number += 1;
+        }
+        Some(c)
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        let c = self.stream.get_char()?;
+        self.stream.unseek();
+        Some(c)
     }
 }
 
@@ -252,67 +368,224 @@ pub mod test {
     #[test]
     fn test_bad_string() {
         let mut tokenizer = Tokenizer::new("\"This is a bad string");
-        let tokens = tokenizer.tokenize();
         assert_eq!(
-            tokens,
-            vec![Token::BadToken {
-                line_number: 1,
-                char_number: 1
-            }],
+            tokenizer.tokenize(),
+            Err(Error::UnterminatedString {
+                position: Position {
+                    line: 1,
+                    column: 1,
+                    offset: 0
+                }
+            }),
         );
     }
 
     #[test]
     fn test_bad_number() {
         let mut tokenizer = Tokenizer::new("342d");
-        let tokens = tokenizer.tokenize();
+        assert_eq!(
+            tokenizer.tokenize(),
+            Err(Error::InvalidNumber {
+                position: Position {
+                    line: 1,
+                    column: 4,
+                    offset: 3
+                }
+            }),
+        );
+    }
+
+    #[test]
+    fn test_number_grammar_violations() {
+        for (input, char_number) in [
+            ("01", 2),
+            ("1.", 3),
+            ("-", 2),
+            ("-a", 2),
+            ("1e", 3),
+            ("1e+", 4),
+            ("2.x", 3),
+            ("2.e5", 3),
+            ("1.5.2", 4),
+        ] {
+            let mut tokenizer = Tokenizer::new(input);
+            assert_eq!(
+                tokenizer.tokenize(),
+                Err(Error::InvalidNumber {
+                    position: Position {
+                        line: 1,
+                        column: char_number,
+                        offset: char_number - 1
+                    }
+                }),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[cfg(not(feature = "arbitrary_precision"))]
+    #[test]
+    fn test_number_overflow() {
+        for (input, column) in [("1e400", 1), ("-1e400", 1), ("[0, 2E+309]", 5)] {
+            assert_eq!(
+                Tokenizer::new(input).tokenize(),
+                Err(Error::InvalidNumber {
+                    position: Position {
+                        line: 1,
+                        column,
+                        offset: column - 1
+                    }
+                }),
+                "input: {}",
+                input
+            );
+        }
+        // Underflow just loses precision, like any other float
+        assert!(Tokenizer::new("1e-400").tokenize().is_ok());
+        assert!(Tokenizer::new("1.7976931348623157e308").tokenize().is_ok());
+    }
+
+    #[test]
+    fn test_non_json_whitespace() {
+        for (input, found, column) in [
+            ("\u{00a0}1", '\u{00a0}', 1),
+            ("\u{000b}1", '\u{000b}', 1),
+            ("\u{feff}[]", '\u{feff}', 1),
+            ("[1,\u{200b}2]", '\u{200b}', 4),
+            ("[true\u{2003}]", '\u{2003}', 6),
+            ("1\u{000c}", '\u{000c}', 2),
+        ] {
+            let error = Tokenizer::new(input).tokenize().unwrap_err();
+            match error {
+                Error::UnexpectedCharacter { found: c, position } => {
+                    assert_eq!((c, position.column), (found, column), "input: {:?}", input)
+                }
+                Error::InvalidNumber { position } => {
+                    assert_eq!(position.column, column, "input: {:?}", input)
+                }
+                _ => panic!("input: {:?}, error: {:?}", input, error),
+            }
+        }
+        assert!(Tokenizer::new(" \t\r\n[1 ,\ttrue\n]\r\n")
+            .tokenize()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_number_forms() {
+        let mut tokenizer = Tokenizer::new("[-3,1e10,\t2.5E-3,\n0.1,-0.5e+2]");
+        let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(
             tokens,
-            vec![Token::BadToken {
-                line_number: 1,
-                char_number: 1
-            }],
+            vec![
+                Token::OpenSquareBrace,
+                Token::Number {
+                    value: Number::from_literal("-3")
+                },
+                Token::Comma,
+                Token::Number {
+                    value: Number::from_literal("1e10")
+                },
+                Token::Comma,
+                Token::Number {
+                    value: Number::from_literal("2.5E-3")
+                },
+                Token::Comma,
+                Token::Number {
+                    value: Number::from_literal("0.1")
+                },
+                Token::Comma,
+                Token::Number {
+                    value: Number::from_literal("-0.5e+2")
+                },
+                Token::ClosedSquareBrace
+            ]
         );
     }
 
     #[test]
     fn test_bad_false() {
         let mut tokenizer = Tokenizer::new("falsf");
-        let tokens = tokenizer.tokenize();
         assert_eq!(
-            tokens,
-            vec![Token::BadToken {
-                line_number: 1,
-                char_number: 1,
-            }]
+            tokenizer.tokenize(),
+            Err(Error::UnexpectedCharacter {
+                found: 'f',
+                position: Position {
+                    line: 1,
+                    column: 5,
+                    offset: 4
+                }
+            })
         );
     }
 
     #[test]
     fn test_bad_true() {
         let mut tokenizer = Tokenizer::new("trud");
-        let tokens = tokenizer.tokenize();
         assert_eq!(
-            tokens,
-            vec![Token::BadToken {
-                line_number: 1,
-                char_number: 1,
-            }]
+            tokenizer.tokenize(),
+            Err(Error::UnexpectedCharacter {
+                found: 'd',
+                position: Position {
+                    line: 1,
+                    column: 4,
+                    offset: 3
+                }
+            })
         );
     }
 
     #[test]
     fn test_line_num() {
-        let mut tokenizer = Tokenizer::new("hey\nbro");
-        tokenizer.tokenize();
+        let mut tokenizer = Tokenizer::new("\"hey\"\n123");
+        tokenizer.tokenize().unwrap();
         assert_eq!(tokenizer.line_number, 2);
         assert_eq!(tokenizer.char_number, 3);
     }
 
+    #[test]
+    fn test_error_position_after_tokens() {
+        let mut tokenizer = Tokenizer::new("[\"é\", 12,\n  true, ?]");
+        assert_eq!(
+            tokenizer.tokenize(),
+            Err(Error::UnexpectedCharacter {
+                found: '?',
+                position: Position {
+                    line: 2,
+                    column: 9,
+                    offset: 19
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_reader_errors() {
+        let input = b"[\"\xc3\xa9\",\n 1, \"a\xff\"]";
+        let mut tokenizer = Tokenizer::from_reader(&input[..], ParseOptions::default());
+        assert_eq!(
+            tokenizer.tokenize(),
+            Err(Error::InvalidUtf8 {
+                position: Position {
+                    line: 2,
+                    column: 7,
+                    offset: 13
+                }
+            })
+        );
+
+        let mut tokenizer =
+            Tokenizer::from_reader("[\"é\", true]".as_bytes(), ParseOptions::default());
+        let tokens = tokenizer.tokenize().unwrap();
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokenizer.position().offset, 12);
+    }
+
     #[test]
     fn test_simple_string() {
         let mut tokenizer = Tokenizer::new("\"Hello World!\"");
-        let tokens = tokenizer.tokenize();
+        let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(
             tokens,
             vec![Token::String {
@@ -321,10 +594,90 @@ pub mod test {
         )
     }
 
+    #[test]
+    fn test_string_escapes() {
+        let mut tokenizer = Tokenizer::new(r#""say \"hi\"\n\t\\ \/ \b\f\r \u00e9\u20AC""#);
+        let tokens = tokenizer.tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::String {
+                value: "say \"hi\"\n\t\\ / \u{8}\u{c}\r é€".to_string()
+            }]
+        )
+    }
+
+    #[test]
+    fn test_surrogate_pair() {
+        let mut tokenizer = Tokenizer::new(r#""\ud83e\udd80""#);
+        let tokens = tokenizer.tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::String {
+                value: "🦀".to_string()
+            }]
+        )
+    }
+
+    #[test]
+    fn test_bad_escapes() {
+        for (input, column) in [
+            (r#""\x""#, 2),
+            (r#""\u12""#, 2),
+            (r#""\ud83e""#, 2),
+            (r#""ab\udd80\ud83e""#, 4),
+            (r#""\ud83e\u0041""#, 2),
+        ] {
+            let mut tokenizer = Tokenizer::new(input);
+            assert_eq!(
+                tokenizer.tokenize(),
+                Err(Error::InvalidEscape {
+                    position: Position {
+                        line: 1,
+                        column,
+                        offset: column - 1
+                    }
+                }),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_control_character_in_string() {
+        let mut tokenizer = Tokenizer::new("\"tab\there\"");
+        assert_eq!(
+            tokenizer.tokenize(),
+            Err(Error::ControlCharacter {
+                position: Position {
+                    line: 1,
+                    column: 5,
+                    offset: 4
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_replace_lone_surrogates() {
+        let options = ParseOptions {
+            replace_lone_surrogates: true,
+            ..ParseOptions::default()
+        };
+        let mut tokenizer = Tokenizer::with_options(r#""\ud83e\u0041 \udd80""#, options);
+        let tokens = tokenizer.tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::String {
+                value: "\u{fffd}A \u{fffd}".to_string()
+            }]
+        )
+    }
+
     #[test]
     fn test_simple_tokens() {
         let mut tokenizer = Tokenizer::new("{\"hi\"}");
-        let tokens = tokenizer.tokenize();
+        let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -340,7 +693,7 @@ pub mod test {
     #[test]
     fn test_true_token() {
         let mut tokenizer = Tokenizer::new("\"true\":true");
-        let tokens = tokenizer.tokenize();
+        let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -356,7 +709,7 @@ pub mod test {
     #[test]
     fn test_false_token() {
         let mut tokenizer = Tokenizer::new("\"false\":false");
-        let tokens = tokenizer.tokenize();
+        let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -369,17 +722,54 @@ pub mod test {
         )
     }
 
+    #[test]
+    fn test_null_token() {
+        let mut tokenizer = Tokenizer::new("\"nothing\":null");
+        let tokens = tokenizer.tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::String {
+                    value: "nothing".to_string()
+                },
+                Token::Colon,
+                Token::Null
+            ]
+        )
+    }
+
+    #[test]
+    fn test_bad_null() {
+        let mut tokenizer = Tokenizer::new("nullx");
+        assert_eq!(
+            tokenizer.tokenize(),
+            Err(Error::UnexpectedCharacter {
+                found: 'x',
+                position: Position {
+                    line: 1,
+                    column: 5,
+                    offset: 4
+                }
+            })
+        );
+    }
+
     #[test]
     fn test_number_token() {
         let mut tokenizer = Tokenizer::new("420");
-        let tokens = tokenizer.tokenize();
-        assert_eq!(tokens, vec![Token::Number { value: 420.0 }]);
+        let tokens = tokenizer.tokenize().unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::Number {
+                value: Number::from(420)
+            }]
+        );
     }
 
     #[test]
     fn test_basic_json_tokenization() {
         let mut tokenizer = Tokenizer::new("{ \"age\" : 23, \"male\" : true }");
-        let tokens = tokenizer.tokenize();
+        let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(
             tokens,
             vec![
@@ -388,7 +778,9 @@ pub mod test {
                     value: "age".to_string()
                 },
                 Token::Colon,
-                Token::Number { value: 23.0 },
+                Token::Number {
+                    value: Number::from(23)
+                },
                 Token::Comma,
                 Token::String {
                     value: "male".to_string()
@@ -403,10 +795,65 @@ pub mod test {
     #[test]
     fn test_square_bracket_token() {
         let mut tokenizer = Tokenizer::new("[]");
-        let tokens = tokenizer.tokenize();
+        let tokens = tokenizer.tokenize().unwrap();
         assert_eq!(
             tokens,
             vec![Token::OpenSquareBrace, Token::ClosedSquareBrace]
         );
     }
+
+    #[test]
+    fn test_token_spans() {
+        let input = "{\"café\": -12.5e3,\n \"ok\": true}";
+        let mut tokenizer = Tokenizer::new(input);
+        let mut spans = vec![];
+        while let Some((_, span)) = tokenizer.next_token().unwrap() {
+            spans.push(span);
+        }
+        let texts: Vec<&str> = spans.iter().map(|span| span.text(input)).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "{",
+                "\"café\"",
+                ":",
+                "-12.5e3",
+                ",",
+                "\"ok\"",
+                ":",
+                "true",
+                "}"
+            ]
+        );
+        assert_eq!(
+            spans[3],
+            Span {
+                start: Position {
+                    line: 1,
+                    column: 10,
+                    offset: 10
+                },
+                end: Position {
+                    line: 1,
+                    column: 17,
+                    offset: 17
+                }
+            }
+        );
+        assert_eq!(
+            spans[7],
+            Span {
+                start: Position {
+                    line: 2,
+                    column: 8,
+                    offset: 26
+                },
+                end: Position {
+                    line: 2,
+                    column: 12,
+                    offset: 30
+                }
+            }
+        );
+    }
 }
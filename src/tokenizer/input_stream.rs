@@ -1,28 +1,134 @@
-pub struct InputStream {
+use std::io::{self, Read};
+
+/// How many bytes are read from a reader at once.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The characters of the input, either all in memory or read from an `io::Read` in chunks.
+pub struct InputStream<'a> {
+    /// All of the input, or the part of it read but not yet consumed from a reader
     pub buffer: Vec<u8>,
     pub index: usize,
+    /// Bytes dropped from the front of `buffer` once they were consumed
+    discarded: usize,
+    reader: Option<Box<dyn Read + 'a>>,
+    /// Where the reader reads into before the bytes are added to `buffer`
+    chunk: Vec<u8>,
+    failure: Option<Failure>,
+}
+
+/// Why a stream stopped before the end of its input.
+pub enum Failure {
+    Io(io::Error),
+    InvalidUtf8,
 }
 
-impl InputStream {
-    pub fn new(input: &str) -> InputStream {
+impl InputStream<'static> {
+    pub fn new(input: &str) -> InputStream<'static> {
         InputStream {
             buffer: Vec::from(input.as_bytes()),
             index: 0,
+            discarded: 0,
+            reader: None,
+            chunk: vec![],
+            failure: None,
+        }
+    }
+}
+
+impl<'a> InputStream<'a> {
+    pub fn from_reader(reader: impl Read + 'a) -> InputStream<'a> {
+        InputStream {
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+            index: 0,
+            discarded: 0,
+            reader: Some(Box::new(reader)),
+            chunk: vec![0; CHUNK_SIZE],
+            failure: None,
         }
     }
 
+    /// Byte offset of the next character in the whole input.
+    pub fn offset(&self) -> usize {
+        self.discarded + self.index
+    }
+
+    /// What stopped the stream early, once it has happened. Later reads give `None`.
+    pub fn take_failure(&mut self) -> Option<Failure> {
+        self.failure.take()
+    }
+
+    /// Steps back over the last character read, however many bytes it took.
     pub fn unseek(&mut self) {
         self.index -= 1;
+        while self.buffer[self.index] & 0b1100_0000 == 0b1000_0000 {
+            self.index -= 1;
+        }
     }
 
     pub fn get_char(&mut self) -> Option<char> {
-        if self.index >= self.buffer.len() {
+        if self.index >= self.buffer.len() && !self.fill(1) {
             return None;
         }
 
-        let c = self.buffer[self.index];
-        self.index += 1;
-        Some(c as char)
+        let width = match self.buffer[self.index] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        let complete = self.index + width <= self.buffer.len() || self.fill(width);
+        let c = match complete {
+            true => std::str::from_utf8(&self.buffer[self.index..self.index + width]).ok(),
+            false => None,
+        };
+        match c.and_then(|c| c.chars().next()) {
+            Some(c) => {
+                self.index += width;
+                Some(c)
+            }
+            // Input from a &str is always valid, so this only happens with a reader
+            None => {
+                self.stop(Failure::InvalidUtf8);
+                None
+            }
+        }
+    }
+
+    /// Reads until at least `needed` bytes are available after `index`, `false` if the
+    /// input ends or fails before that.
+    fn fill(&mut self, needed: usize) -> bool {
+        let reader = match self.reader.as_mut() {
+            Some(reader) => reader,
+            None => return false,
+        };
+        // Keep the last character for `unseek`, a character is at most 4 bytes
+        let consumed = self.index.saturating_sub(4);
+        self.buffer.drain(..consumed);
+        self.discarded += consumed;
+        self.index -= consumed;
+
+        while self.buffer.len() < self.index + needed {
+            match reader.read(&mut self.chunk) {
+                Ok(0) => {
+                    self.reader = None;
+                    return false;
+                }
+                Ok(read) => self.buffer.extend_from_slice(&self.chunk[..read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.stop(Failure::Io(e));
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn stop(&mut self, failure: Failure) {
+        self.reader = None;
+        // Nothing after the failure can be read
+        self.buffer.truncate(self.index);
+        self.failure = Some(failure);
     }
 }
 
@@ -52,4 +158,85 @@ pub mod test {
         stream.get_char();
         assert!(stream.get_char().is_none());
     }
+
+    #[test]
+    fn test_multi_byte_chars() {
+        let mut stream = InputStream::new("é€🦀");
+        assert_eq!(stream.get_char().unwrap(), 'é');
+        assert_eq!(stream.get_char().unwrap(), '€');
+        assert_eq!(stream.get_char().unwrap(), '🦀');
+        assert!(stream.get_char().is_none());
+    }
+
+    /// Gives its bytes one at a time, so characters are split across reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((first, rest)) => {
+                    buf[0] = *first;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reader() {
+        let text = "a🦀é".repeat(CHUNK_SIZE);
+        let mut stream = InputStream::from_reader(Trickle(text.as_bytes()));
+        let mut read = String::new();
+        while let Some(c) = stream.get_char() {
+            read.push(c);
+            stream.unseek();
+            assert_eq!(stream.get_char(), Some(c));
+        }
+        assert_eq!(read, text);
+        assert_eq!(stream.offset(), text.len());
+        assert!(stream.buffer.len() < CHUNK_SIZE * 2);
+        assert!(stream.take_failure().is_none());
+    }
+
+    #[test]
+    fn test_reader_failures() {
+        let mut stream = InputStream::from_reader(Trickle(b"ab\xffc"));
+        assert_eq!(stream.get_char(), Some('a'));
+        assert_eq!(stream.get_char(), Some('b'));
+        assert_eq!(stream.get_char(), None);
+        assert_eq!(stream.offset(), 2);
+        assert!(matches!(stream.take_failure(), Some(Failure::InvalidUtf8)));
+        assert_eq!(stream.get_char(), None);
+
+        // A character cut off by the end of the input
+        let mut stream = InputStream::from_reader(&"é".as_bytes()[..1]);
+        assert_eq!(stream.get_char(), None);
+        assert!(matches!(stream.take_failure(), Some(Failure::InvalidUtf8)));
+
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+            }
+        }
+        let mut stream = InputStream::from_reader(b"x".chain(Broken));
+        assert_eq!(stream.get_char(), Some('x'));
+        assert_eq!(stream.get_char(), None);
+        match stream.take_failure() {
+            Some(Failure::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::ConnectionReset),
+            _ => panic!("expected a read error"),
+        }
+    }
+
+    #[test]
+    fn test_unseek_multi_byte_char() {
+        let mut stream = InputStream::new("a🦀");
+        stream.get_char();
+        stream.get_char();
+        stream.unseek();
+        assert_eq!(stream.index, 1);
+        assert_eq!(stream.get_char().unwrap(), '🦀');
+    }
 }
@@ -1,11 +1,25 @@
+use crate::error::Position;
+use crate::number::Number;
+use std::fmt;
+
+/// The part of the input a token was read from, `end` is right after its last character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// The source text the span covers, `source` has to be the tokenized input.
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start.offset..self.end.offset]
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
-    String {
-        value: String,
-    },
-    Number {
-        value: f64,
-    },
+    String { value: String },
+    Number { value: Number },
     Colon,
     Comma,
     OpenCurlyBrace,
@@ -15,8 +29,22 @@ pub enum Token {
     True,
     False,
     Null,
-    BadToken {
-        line_number: usize,
-        char_number: usize,
-    },
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::String { value } => write!(f, "the string {:?}", value),
+            Token::Number { value } => write!(f, "the number {}", value),
+            Token::Colon => write!(f, "':'"),
+            Token::Comma => write!(f, "','"),
+            Token::OpenCurlyBrace => write!(f, "'{{'"),
+            Token::ClosedCurlyBrace => write!(f, "'}}'"),
+            Token::OpenSquareBrace => write!(f, "'['"),
+            Token::ClosedSquareBrace => write!(f, "']'"),
+            Token::True => write!(f, "'true'"),
+            Token::False => write!(f, "'false'"),
+            Token::Null => write!(f, "'null'"),
+        }
+    }
 }
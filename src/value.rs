@@ -1,13 +1,19 @@
+use crate::error::Error;
+use crate::map::Map;
+use crate::number::Number;
+use crate::serializer;
 use std::clone::Clone;
-use std::collections::HashMap;
-use std::error::Error;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::{Index, IndexMut};
 
 #[derive(PartialEq, Debug)]
 pub enum Value {
-    Object(HashMap<String, Value>),
+    Object(Map),
     Array(Vec<Value>),
     String(String),
-    Number(f64),
+    Number(Number),
     Bool(bool),
     Null,
 }
@@ -20,45 +26,175 @@ impl Clone for Value {
             Value::Number(num) => Value::Number(num.clone()),
             Value::String(string) => Value::String(string.clone()),
             Value::Null => Value::Null,
-            Value::Bool(boo) => Value::Bool(boo.clone()),
+            Value::Bool(boo) => Value::Bool(*boo),
         }
     }
 }
 
+/// Formats the value as compact JSON text.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        serializer::write_value(self, f)
+    }
+}
+
 impl Value {
-    pub fn get_obj(self) -> Result<HashMap<String, Value>, Box<dyn Error>> {
+    pub fn get_obj(self) -> Result<Map, Error> {
         if let Value::Object(obj) = self {
             return Ok(obj);
         }
-        Err("The value you are trying to extract is not an object!")?
+        Err(self.type_error("an object"))
     }
 
-    pub fn get_arr(self) -> Result<Vec<Value>, Box<dyn Error>> {
+    pub fn get_arr(self) -> Result<Vec<Value>, Error> {
         if let Value::Array(vec) = self {
             return Ok(vec);
         }
-        Err("The value you are trying to extract is not an Array!")?
+        Err(self.type_error("an array"))
     }
 
-    pub fn get_str(self) -> Result<String, Box<dyn Error>> {
+    pub fn get_str(self) -> Result<String, Error> {
         if let Value::String(str) = self {
             return Ok(str);
         }
-        Err("The value you are trying to extract is not a String!")?
+        Err(self.type_error("a string"))
     }
 
-    pub fn get_num(self) -> Result<f64, Box<dyn Error>> {
+    /// Integers above 2^53 get rounded, match on `Value::Number` to get them exactly.
+    pub fn get_num(self) -> Result<f64, Error> {
         if let Value::Number(num) = self {
-            return Ok(num);
+            return Ok(num.as_f64());
         }
-        Err("The value you are trying to extract is not a Number!")?
+        Err(self.type_error("a number"))
     }
 
-    pub fn get_bool(self) -> Result<bool, Box<dyn Error>> {
+    pub fn get_bool(self) -> Result<bool, Error> {
         if let Value::Bool(boo) = self {
             return Ok(boo);
         }
-        Err("The value you are trying to extract is not a Bool!")?
+        Err(self.type_error("a bool"))
+    }
+
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            Value::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            Value::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_string_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(num) => Some(num),
+            _ => None,
+        }
+    }
+
+    /// Integers above 2^53 get rounded, use `as_i64` or `as_u64` to get them exactly.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(Number::as_f64)
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number()?.as_i64()
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()?.as_u64()
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(boo) => Some(*boo),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool_mut(&mut self) -> Option<&mut bool> {
+        match self {
+            Value::Bool(boo) => Some(boo),
+            _ => None,
+        }
+    }
+
+    /// A `TypeMismatch` for extracting this value as `expected`, at the root of the path.
+    /// Meant for `FromJson` implementations.
+    pub fn type_error(&self, expected: &'static str) -> Error {
+        Error::TypeMismatch {
+            expected,
+            found: self.type_name(),
+            path: String::new(),
+        }
+    }
+
+    /// Name of the held type for error messages, e.g. "an object".
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Object(_) => "an object",
+            Value::Array(_) => "an array",
+            Value::String(_) => "a string",
+            Value::Number(_) => "a number",
+            Value::Bool(_) => "a bool",
+            Value::Null => "null",
+        }
+    }
+
+    /// Equality as JSON sees it: like `==`, except that numbers are equal when their values
+    /// are, so `1` equals `1.0`.
+    pub(crate) fn json_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(left), Value::Number(right)) => {
+                left.compare(right) == Some(Ordering::Equal)
+            }
+            (Value::Array(left), Value::Array(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|(left, right)| left.json_eq(right))
+            }
+            (Value::Object(left), Value::Object(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .all(|(key, left)| right.get(key).is_some_and(|right| left.json_eq(right)))
+            }
+            _ => self == other,
+        }
     }
 
     pub fn is_null(&self) -> bool {
@@ -68,3 +204,205 @@ impl Value {
         false
     }
 }
+
+impl From<bool> for Value {
+    fn from(boo: bool) -> Self {
+        Value::Bool(boo)
+    }
+}
+
+impl From<String> for Value {
+    fn from(string: String) -> Self {
+        Value::String(string)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(string: &str) -> Self {
+        Value::String(string.to_string())
+    }
+}
+
+impl From<Number> for Value {
+    fn from(num: Number) -> Self {
+        Value::Number(num)
+    }
+}
+
+macro_rules! from_number {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(num: $ty) -> Self {
+                    Value::Number(num.into())
+                }
+            }
+        )*
+    };
+}
+
+from_number!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize f32 f64);
+
+impl From<Map> for Value {
+    fn from(obj: Map) -> Self {
+        Value::Object(obj)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(vec: Vec<T>) -> Self {
+        Value::Array(vec.into_iter().map(Into::into).collect())
+    }
+}
+
+/// `None` becomes `Null`.
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(Value::Null, Into::into)
+    }
+}
+
+/// The members end up in the order the `HashMap` iterates them, which is arbitrary.
+impl<K: Into<String>, V: Into<Value>> From<HashMap<K, V>> for Value {
+    fn from(map: HashMap<K, V>) -> Self {
+        Value::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> From<BTreeMap<K, V>> for Value {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        Value::Object(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+/// Returned by `Index` for keys and indices that are not there.
+static NULL: Value = Value::Null;
+
+/// `value["key"]` gives the member of an object, or `Null` if the key is missing or the
+/// value is not an object, so deep paths like `value["a"]["b"][0]` never panic.
+impl Index<&str> for Value {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(obj) => obj.get(key).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// `value[i]` gives the element of an array, or `Null` if it is out of bounds or the
+/// value is not an array.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        match self {
+            Value::Array(arr) => arr.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// Inserts `Null` for a missing key and turns a `Null` value into an empty object first,
+/// so `value["a"]["b"] = ...` builds the objects on the way.
+///
+/// Panics if the value is neither an object nor `Null`.
+impl IndexMut<&str> for Value {
+    fn index_mut(&mut self, key: &str) -> &mut Value {
+        if let Value::Null = self {
+            *self = Value::Object(Map::new());
+        }
+        match self {
+            Value::Object(obj) => {
+                if !obj.contains_key(key) {
+                    obj.insert(key, Value::Null);
+                }
+                // The key was inserted above if it was missing
+                obj.get_mut(key).unwrap()
+            }
+            _ => panic!(
+                "Can not index into {} with the key {:?}",
+                self.type_name(),
+                key
+            ),
+        }
+    }
+}
+
+/// Panics if the value is not an array or the index is out of bounds, like `Vec` does.
+impl IndexMut<usize> for Value {
+    fn index_mut(&mut self, index: usize) -> &mut Value {
+        match self {
+            Value::Array(arr) => {
+                let len = arr.len();
+                match arr.get_mut(index) {
+                    Some(value) => value,
+                    None => panic!("Index {} is out of bounds for an array of {}", index, len),
+                }
+            }
+            _ => panic!(
+                "Can not index into {} with the index {}",
+                self.type_name(),
+                index
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn test_borrowed_accessors() {
+        let mut value = crate::parse("{\"name\":\"Mike\",\"tags\":[1,true],\"ok\":false}").unwrap();
+        assert_eq!(value["name"].as_str(), Some("Mike"));
+        assert_eq!(value["tags"][0].as_i64(), Some(1));
+        assert_eq!(value["tags"][0].as_f64(), Some(1.0));
+        assert_eq!(value["tags"][1].as_bool(), Some(true));
+        assert_eq!(value["name"].as_f64(), None);
+        assert_eq!(value.as_object().map(Map::len), Some(3));
+        assert_eq!(value.as_array(), None);
+
+        value["tags"].as_array_mut().unwrap().push(Value::Null);
+        *value["ok"].as_bool_mut().unwrap() = true;
+        value["name"].as_string_mut().unwrap().push_str(" Wazowski");
+        assert_eq!(
+            value.to_string(),
+            "{\"name\":\"Mike Wazowski\",\"tags\":[1,true,null],\"ok\":true}"
+        );
+    }
+
+    #[test]
+    fn test_index_missing_is_null() {
+        let value = crate::parse("{\"a\":[{\"b\":1}]}").unwrap();
+        assert_eq!(value["a"][0]["b"], Value::Number(1.into()));
+        assert!(value["a"][5]["b"].is_null());
+        assert!(value["x"]["y"][0].is_null());
+        assert!(value[0].is_null());
+    }
+
+    #[test]
+    fn test_index_mut_creates_objects() {
+        let mut value = Value::Null;
+        value["a"]["b"] = Value::Bool(true);
+        value["a"]["c"] = Value::Array(vec![Value::Null]);
+        value["a"]["c"][0] = Value::String("x".to_string());
+        assert_eq!(value.to_string(), "{\"a\":{\"b\":true,\"c\":[\"x\"]}}");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_mut_out_of_bounds() {
+        let mut value = Value::Array(vec![]);
+        value[0] = Value::Null;
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_mut_wrong_type() {
+        let mut value = Value::Bool(false);
+        value["a"] = Value::Null;
+    }
+}
@@ -1,4 +1,4 @@
-use jsonlib;
+use jsonlib::stream::{Event, Reader};
 use jsonlib::value::Value;
 use std::fs;
 
@@ -29,3 +29,288 @@ fn test_parsing_02() {
         panic!("Value is not an object!");
     }
 }
+
+#[test]
+fn test_pointer_lookup() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let mut obj = jsonlib::parse(data.as_str()).expect("should parse no problem");
+    assert_eq!(
+        obj.pointer("/glossary/GlossDiv/title"),
+        Some(&Value::String("S".to_string()))
+    );
+    assert_eq!(obj.pointer("/glossary/GlossDiv/missing"), None);
+
+    let old = obj
+        .pointer_set("/glossary/title", Value::from("renamed"))
+        .expect("parent should exist");
+    assert_eq!(old, Some(Value::String("example glossary".to_string())));
+    assert_eq!(obj["glossary"]["title"], Value::from("renamed"));
+}
+
+#[test]
+#[cfg(feature = "jsonpath")]
+fn test_path_query() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let obj = jsonlib::parse(data.as_str()).expect("should parse no problem");
+    let query = jsonlib::path::compile("$..[?@.Abbrev && length(@.ID) == 4].GlossTerm")
+        .expect("should compile no problem");
+    let found = query.query(&obj);
+    assert_eq!(found.len(), 1);
+    assert_eq!(
+        found[0].path(),
+        "$['glossary']['GlossDiv']['GlossList']['GlossEntry']['GlossTerm']"
+    );
+    assert_eq!(
+        found[0].value(),
+        &Value::String("Standard Generalized Markup Language".to_string())
+    );
+}
+
+#[test]
+fn test_apply_patch() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let mut obj = jsonlib::parse(data.as_str()).expect("should parse no problem");
+    let patch = jsonlib::parse(
+        r#"[
+            {"op": "test", "path": "/glossary/GlossDiv/title", "value": "S"},
+            {"op": "copy", "from": "/glossary/GlossDiv/title", "path": "/glossary/letter"},
+            {"op": "remove", "path": "/glossary/GlossDiv"}
+        ]"#,
+    )
+    .expect("should parse no problem");
+    jsonlib::patch::apply(&mut obj, &patch).expect("should apply no problem");
+    assert_eq!(
+        obj.to_string(),
+        "{\"glossary\":{\"title\":\"example glossary\",\"letter\":\"S\"}}"
+    );
+
+    let err = jsonlib::patch::apply(&mut obj, &patch).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "JSON Patch operation 0 failed at \"/glossary/GlossDiv/title\": there is no value at the pointer"
+    );
+}
+
+#[test]
+fn test_diff() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let from = jsonlib::parse(data.as_str()).expect("should parse no problem");
+    let mut to = from.clone();
+    to.pointer_set("/glossary/title", Value::from("renamed"))
+        .expect("should set no problem");
+    to.pointer_remove("/glossary/GlossDiv/title");
+
+    let patch = jsonlib::diff(&from, &to);
+    assert_eq!(
+        patch.to_string(),
+        "[{\"op\":\"replace\",\"path\":\"/glossary/title\",\"value\":\"renamed\"},\
+         {\"op\":\"remove\",\"path\":\"/glossary/GlossDiv/title\"}]"
+    );
+    let mut patched = from.clone();
+    jsonlib::patch::apply(&mut patched, &patch).expect("should apply no problem");
+    assert_eq!(patched, to);
+}
+
+#[test]
+fn test_merge_patch() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let from = jsonlib::parse(data.as_str()).expect("should parse no problem");
+    let mut to = from.clone();
+    to.pointer_set("/glossary/GlossDiv/title", Value::from("T"))
+        .expect("should set no problem");
+    to.pointer_remove("/glossary/title");
+
+    let patch = jsonlib::merge_patch_diff(&from, &to);
+    assert_eq!(
+        patch.to_string(),
+        "{\"glossary\":{\"title\":null,\"GlossDiv\":{\"title\":\"T\"}}}"
+    );
+    let mut merged = from.clone();
+    merged.merge_patch(&patch);
+    assert_eq!(merged, to);
+}
+
+#[test]
+fn test_stream_reader() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let mut reader = Reader::new(data.as_str());
+    let mut keys = vec![];
+    while let Some(event) = reader.next_event().expect("should read no problem") {
+        match event {
+            Event::Key("GlossDiv") => {
+                assert_eq!(reader.next_event().unwrap(), Some(Event::StartObject));
+                reader.skip().expect("should skip no problem");
+            }
+            Event::Key(key) => keys.push(key.to_string()),
+            _ => {}
+        }
+    }
+    assert_eq!(keys, ["glossary", "title"]);
+}
+
+#[test]
+fn test_parse_reader() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let file = fs::File::open("tests/data.json").expect("Should be able to open");
+    let obj = jsonlib::parse_reader(file).expect("should parse no problem");
+    assert_eq!(obj, jsonlib::parse(data.as_str()).unwrap());
+
+    let title: String = jsonlib::from_reader("\"example\"".as_bytes()).unwrap();
+    assert_eq!(title, "example");
+
+    let err = jsonlib::parse_reader(&b"{\"a\": [1, 2]} x"[..]).unwrap_err();
+    assert_eq!(err.position().map(|position| position.offset), Some(14));
+    let err = jsonlib::parse_reader(&b"[\"\xe9t\xe9\"]"[..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid UTF-8 at byte offset 2 (line 1, column 3)"
+    );
+
+    struct Reset;
+    impl std::io::Read for Reset {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::ConnectionReset.into())
+        }
+    }
+    let err = jsonlib::parse_reader(std::io::Read::chain(&b"[1,\n2"[..], Reset)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Reading the input failed at byte offset 5 (line 2, column 2): connection reset"
+    );
+}
+
+#[test]
+fn test_non_ascii_text() {
+    let obj = jsonlib::parse("{\"café\": \"crème brûlée 🦀\"}").expect("should parse no problem");
+    if let Value::Object(o) = &obj {
+        assert_eq!(o["café"], Value::String("crème brûlée 🦀".to_string()));
+    } else {
+        panic!("Value is not an object!");
+    }
+}
+
+#[test]
+fn test_parse_bytes() {
+    let obj = jsonlib::parse_bytes("[\"naïve\"]".as_bytes()).expect("should parse no problem");
+    assert_eq!(obj, Value::Array(vec![Value::String("naïve".to_string())]));
+}
+
+#[test]
+fn test_parse_invalid_utf8() {
+    let err = jsonlib::parse_bytes(b"[\"ok\",\n \"\xff\"]").unwrap_err();
+    assert_eq!(
+        err,
+        jsonlib::Error::InvalidUtf8 {
+            position: jsonlib::Position {
+                line: 2,
+                column: 3,
+                offset: 9
+            }
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Invalid UTF-8 at byte offset 9 (line 2, column 3)"
+    );
+}
+
+#[test]
+fn test_parse_whole_document() {
+    assert_eq!(
+        jsonlib::parse("\"just a string\"").expect("any value can be the root"),
+        Value::String("just a string".to_string())
+    );
+    assert!(jsonlib::parse("{\"a\": 1} {\"b\": 2}").is_err());
+    // Only space, tab, line feed and carriage return are whitespace in JSON
+    assert!(jsonlib::parse(" \t1\r\n").is_ok());
+    for input in ["\u{00a0}1", "\u{000b}1", "[true\u{2003}]", "\u{feff}1"] {
+        assert!(jsonlib::parse(input).is_err(), "{:?}", input);
+    }
+}
+
+#[test]
+fn test_to_writer_round_trip() {
+    let data = fs::read_to_string("tests/data.json").expect("Should be able to open");
+    let obj = jsonlib::parse(data.as_str()).expect("should parse no problem");
+    let mut out = Vec::new();
+    jsonlib::to_writer(&obj, &mut out).expect("writing to a Vec can not fail");
+    let text = String::from_utf8(out).expect("output is UTF-8");
+    assert!(!text.contains('\n'));
+    assert_eq!(jsonlib::parse(&text).expect("output is valid JSON"), obj);
+}
+
+#[test]
+fn test_to_string_pretty() {
+    let obj = jsonlib::parse("{\"tags\": [\"a\", \"b\"]}").expect("should parse no problem");
+    let config = jsonlib::PrettyConfig {
+        indent: "    ".to_string(),
+        trailing_newline: true,
+        ..jsonlib::PrettyConfig::default()
+    };
+    assert_eq!(
+        jsonlib::to_string_pretty(&obj, &config),
+        "{\n    \"tags\": [\n        \"a\",\n        \"b\"\n    ]\n}\n"
+    );
+}
+
+#[test]
+fn test_key_order_round_trip() {
+    let source = "{\"zeta\":1,\"alpha\":{\"y\":true,\"b\":null},\"mid\":[]}";
+    let mut obj = jsonlib::parse(source).expect("should parse no problem");
+    assert_eq!(obj.to_string(), source);
+    if let Value::Object(o) = &mut obj {
+        o.shift_remove("alpha");
+        o.insert("new", Value::Bool(false));
+    }
+    assert_eq!(obj.to_string(), "{\"zeta\":1,\"mid\":[],\"new\":false}");
+}
+
+#[test]
+fn test_large_integer_ids() {
+    let source = "{\"id\":1234567890123456789,\"max\":18446744073709551615,\"price\":2.0}";
+    let obj = jsonlib::parse(source).expect("should parse no problem");
+    if let Value::Object(o) = &obj {
+        if let Value::Number(id) = &o["id"] {
+            assert_eq!(id.as_i64(), Some(1234567890123456789));
+        } else {
+            panic!("id is not a number!");
+        }
+        if let Value::Number(max) = &o["max"] {
+            assert_eq!(max.as_u64(), Some(u64::MAX));
+        } else {
+            panic!("max is not a number!");
+        }
+    } else {
+        panic!("Value is not an object!");
+    }
+    assert_eq!(obj.to_string(), source);
+}
+
+#[test]
+#[cfg(feature = "arbitrary_precision")]
+fn test_exact_number_round_trip() {
+    let source = "[0.1000000000000000055511151231257827,123456789012345678901234567890,1E+2,-0.0]";
+    let arr = jsonlib::parse(source).expect("should parse no problem");
+    assert_eq!(arr.to_string(), source);
+}
+
+#[test]
+fn test_typed_conversion() {
+    use std::collections::HashMap;
+
+    let source = "{\"servers\":{\"alpha\":[8080,8081],\"beta\":[443]}}";
+    let config: HashMap<String, HashMap<String, Vec<u16>>> =
+        jsonlib::from_str(source).expect("should convert no problem");
+    assert_eq!(config["servers"]["beta"], vec![443]);
+    let beta = &config["servers"]["beta"];
+    assert_eq!(jsonlib::to_string(beta), "[443]");
+
+    let err = jsonlib::from_str::<HashMap<String, HashMap<String, Vec<u16>>>>(
+        "{\"servers\":{\"alpha\":[8080,70000]}}",
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The value at $.servers.alpha[1] is not a u16, it is an integer out of range!"
+    );
+}